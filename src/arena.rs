@@ -0,0 +1,99 @@
+//! Storage of the pages, nodes and arcs of a net.
+//!
+//! The arena hands out keys that stay valid until the object is removed, no matter what
+//! happens to other objects. Slots of removed objects are reused with a new generation,
+//! so an old key is detected as stale instead of pointing at the new object.
+//! Keys also remember their arena, so a key of another net is not found either.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// source of the ids of all arenas
+static NEXT_ARENA: AtomicU32 = AtomicU32::new(0);
+
+/// Position of an object in an [`Arena`]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub struct Key {
+    arena: u32,
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Arena<T> {
+    id: u32,
+    slots: Vec<Slot<T>>,
+    /// indices of the empty slots
+    free: Vec<u32>,
+}
+
+impl Key {
+    pub(crate) fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub(crate) fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Arena<T> {
+    pub(crate) fn new() -> Self {
+        Arena {
+            id: NEXT_ARENA.fetch_add(1, Ordering::Relaxed),
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, value: T) -> Key {
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].value = Some(value);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        Key {
+            arena: self.id,
+            index,
+            generation: self.slots[index as usize].generation,
+        }
+    }
+
+    fn slot(&self, key: Key) -> Option<&Slot<T>> {
+        self.slots
+            .get(key.index as usize)
+            .filter(|slot| key.arena == self.id && slot.generation == key.generation)
+    }
+
+    pub(crate) fn get(&self, key: Key) -> Option<&T> {
+        self.slot(key)?.value.as_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        self.slot(key)?;
+        self.slots[key.index as usize].value.as_mut()
+    }
+
+    /// Takes the object out of the arena. The key and all copies of it are stale afterwards.
+    pub(crate) fn remove(&mut self, key: Key) -> Option<T> {
+        self.slot(key)?;
+        let slot = &mut self.slots[key.index as usize];
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index);
+        Some(value)
+    }
+}
@@ -0,0 +1,306 @@
+//! Structural classes of place/transition nets.
+//!
+//! Many analysis results only hold for nets of a certain structure. [`PetriNet::classify`]
+//! tells which of the classes in [`NetClass`] a net belongs to and gives a [`ClassWitness`]
+//! for every class it misses. State machines, marked graphs and the choice classes are
+//! defined for ordinary nets, a net with an arc weight above one misses all of them.
+
+use crate::compiled::CompiledNet;
+use crate::*;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum NetClass {
+    /// every arc has the weight one
+    Ordinary,
+    /// no transition consumes from and produces on the same place
+    Pure,
+    /// there are positive weights of the places such that the weighted sum of tokens never changes
+    Conservative,
+    /// every transition has exactly one input and one output place
+    StateMachine,
+    /// every place has exactly one input and one output transition
+    MarkedGraph,
+    /// a place with several output transitions is their only input place
+    FreeChoice,
+    /// places with a common output transition have the same output transitions
+    ExtendedFreeChoice,
+    /// of two places with a common output transition the output transitions of one
+    /// include those of the other
+    AsymmetricChoice,
+}
+
+impl NetClass {
+    pub const ALL: [NetClass; 8] = [
+        NetClass::Ordinary,
+        NetClass::Pure,
+        NetClass::Conservative,
+        NetClass::StateMachine,
+        NetClass::MarkedGraph,
+        NetClass::FreeChoice,
+        NetClass::ExtendedFreeChoice,
+        NetClass::AsymmetricChoice,
+    ];
+}
+
+/// The reason a net is not of a class, with the ids of the nodes involved
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ClassWitness {
+    /// the net is not ordinary
+    ArcWeight {
+        source: String,
+        target: String,
+        weight: usize,
+    },
+    /// a transition of a state machine needs one input and one output place
+    TransitionArity {
+        transition: String,
+        inputs: usize,
+        outputs: usize,
+    },
+    /// a place of a marked graph needs one input and one output transition
+    PlaceArity {
+        place: String,
+        inputs: usize,
+        outputs: usize,
+    },
+    /// the place has several output transitions and the transition has another input place
+    SharedPlace {
+        place: String,
+        transition: String,
+        other_place: String,
+    },
+    /// both places are inputs of `shared`, but `only_first` is an output transition of the
+    /// first place only
+    DifferentPostsets {
+        first: String,
+        second: String,
+        shared: String,
+        only_first: String,
+    },
+    /// both places are inputs of `shared`, but neither output transitions include the others
+    CrossingPostsets {
+        first: String,
+        second: String,
+        shared: String,
+        only_first: String,
+        only_second: String,
+    },
+    /// the transition consumes from and produces on the place
+    SelfLoop { place: String, transition: String },
+    /// no P-semiflow has a weight on the place
+    UncoveredPlace { place: String },
+}
+
+/// The classes a net belongs to, see the [module documentation](self)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Classification {
+    /// the class with None if the net belongs to it, in the order of [`NetClass::ALL`]
+    results: Vec<(NetClass, Option<ClassWitness>)>,
+}
+
+impl Classification {
+    pub fn is(&self, class: NetClass) -> bool {
+        self.witness(class).is_none()
+    }
+
+    /// the classes of the net in the order of [`NetClass::ALL`]
+    pub fn classes(&self) -> Vec<NetClass> {
+        self.results
+            .iter()
+            .filter(|(_, witness)| witness.is_none())
+            .map(|(class, _)| *class)
+            .collect()
+    }
+
+    /// why the net is not of the class, None if it is
+    pub fn witness(&self, class: NetClass) -> Option<&ClassWitness> {
+        self.results
+            .iter()
+            .find(|(other, _)| *other == class)
+            .and_then(|(_, witness)| witness.as_ref())
+    }
+}
+
+impl PetriNet {
+    /// Checks the classes of the net, see [`Classification`]
+    pub fn classify(&self) -> Result<Classification> {
+        self.compile()?.classify()
+    }
+}
+
+impl CompiledNet {
+    /// Checks the classes of the net, see [`Classification`].
+    /// Fails if the P-semiflows needed for conservativeness are too large to compute.
+    pub fn classify(&self) -> Result<Classification> {
+        let ordinary = self.ordinary_witness();
+        let unless_ordinary =
+            |witness: fn(&Self) -> Option<ClassWitness>| ordinary.clone().or_else(|| witness(self));
+        let results = vec![
+            (NetClass::Ordinary, ordinary.clone()),
+            (NetClass::Pure, self.pure_witness()),
+            (NetClass::Conservative, self.conservative_witness()?),
+            (
+                NetClass::StateMachine,
+                unless_ordinary(Self::state_machine_witness),
+            ),
+            (
+                NetClass::MarkedGraph,
+                unless_ordinary(Self::marked_graph_witness),
+            ),
+            (
+                NetClass::FreeChoice,
+                unless_ordinary(Self::free_choice_witness),
+            ),
+            (
+                NetClass::ExtendedFreeChoice,
+                unless_ordinary(Self::extended_free_choice_witness),
+            ),
+            (
+                NetClass::AsymmetricChoice,
+                unless_ordinary(Self::asymmetric_choice_witness),
+            ),
+        ];
+        Ok(Classification { results })
+    }
+
+    pub(crate) fn ordinary_witness(&self) -> Option<ClassWitness> {
+        for t in 0..self.transition_count() {
+            if let Some((p, weight)) = self.pre(t).find(|(_, weight)| *weight > 1) {
+                return Some(ClassWitness::ArcWeight {
+                    source: self.place_id(p).into(),
+                    target: self.transition_id(t).into(),
+                    weight,
+                });
+            }
+            if let Some((p, weight)) = self.post(t).find(|(_, weight)| *weight > 1) {
+                return Some(ClassWitness::ArcWeight {
+                    source: self.transition_id(t).into(),
+                    target: self.place_id(p).into(),
+                    weight,
+                });
+            }
+        }
+        None
+    }
+
+    fn pure_witness(&self) -> Option<ClassWitness> {
+        (0..self.transition_count()).find_map(|t| {
+            self.pre(t)
+                .find(|(p, _)| self.post(t).any(|(output, _)| output == *p))
+                .map(|(p, _)| ClassWitness::SelfLoop {
+                    place: self.place_id(p).into(),
+                    transition: self.transition_id(t).into(),
+                })
+        })
+    }
+
+    /// a positive weighting exists if every place is part of a minimal P-semiflow
+    fn conservative_witness(&self) -> Result<Option<ClassWitness>> {
+        let semiflows = self.p_semiflows()?;
+        Ok((0..self.place_count())
+            .find(|p| {
+                !semiflows
+                    .iter()
+                    .any(|semiflow| semiflow.entries().iter().any(|entry| entry.index == *p))
+            })
+            .map(|p| ClassWitness::UncoveredPlace {
+                place: self.place_id(p).into(),
+            }))
+    }
+
+    fn state_machine_witness(&self) -> Option<ClassWitness> {
+        (0..self.transition_count()).find_map(|t| {
+            let (inputs, outputs) = (self.pre(t).count(), self.post(t).count());
+            if inputs == 1 && outputs == 1 {
+                return None;
+            }
+            Some(ClassWitness::TransitionArity {
+                transition: self.transition_id(t).into(),
+                inputs,
+                outputs,
+            })
+        })
+    }
+
+    fn marked_graph_witness(&self) -> Option<ClassWitness> {
+        (0..self.place_count()).find_map(|p| {
+            let (inputs, outputs) = (self.place_pre(p).count(), self.place_post(p).count());
+            if inputs == 1 && outputs == 1 {
+                return None;
+            }
+            Some(ClassWitness::PlaceArity {
+                place: self.place_id(p).into(),
+                inputs,
+                outputs,
+            })
+        })
+    }
+
+    fn free_choice_witness(&self) -> Option<ClassWitness> {
+        (0..self.place_count())
+            .filter(|p| self.place_post(*p).count() > 1)
+            .find_map(|p| {
+                self.place_post(p).find_map(|(t, _)| {
+                    let (other, _) = self.pre(t).find(|(other, _)| *other != p)?;
+                    Some(ClassWitness::SharedPlace {
+                        place: self.place_id(p).into(),
+                        transition: self.transition_id(t).into(),
+                        other_place: self.place_id(other).into(),
+                    })
+                })
+            })
+    }
+
+    pub(crate) fn extended_free_choice_witness(&self) -> Option<ClassWitness> {
+        self.overlapping_places()
+            .into_iter()
+            .find_map(|(first, second, shared)| {
+                let only_first = self.only_first(first, second)?;
+                Some(ClassWitness::DifferentPostsets {
+                    first: self.place_id(first).into(),
+                    second: self.place_id(second).into(),
+                    shared: self.transition_id(shared).into(),
+                    only_first: self.transition_id(only_first).into(),
+                })
+            })
+    }
+
+    fn asymmetric_choice_witness(&self) -> Option<ClassWitness> {
+        self.overlapping_places()
+            .into_iter()
+            .find_map(|(first, second, shared)| {
+                let only_first = self.only_first(first, second)?;
+                let only_second = self.only_first(second, first)?;
+                Some(ClassWitness::CrossingPostsets {
+                    first: self.place_id(first).into(),
+                    second: self.place_id(second).into(),
+                    shared: self.transition_id(shared).into(),
+                    only_first: self.transition_id(only_first).into(),
+                    only_second: self.transition_id(only_second).into(),
+                })
+            })
+    }
+
+    /// all ordered pairs of different places with a common output transition
+    fn overlapping_places(&self) -> Vec<(usize, usize, usize)> {
+        let mut pairs = Vec::new();
+        for first in 0..self.place_count() {
+            for second in (0..self.place_count()).filter(|second| *second != first) {
+                let shared = self
+                    .place_post(first)
+                    .find(|(t, _)| self.place_post(second).any(|(other, _)| other == *t));
+                if let Some((shared, _)) = shared {
+                    pairs.push((first, second, shared));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// an output transition of the first place that is none of the second
+    fn only_first(&self, first: usize, second: usize) -> Option<usize> {
+        self.place_post(first)
+            .map(|(t, _)| t)
+            .find(|t| !self.place_post(second).any(|(other, _)| other == *t))
+    }
+}
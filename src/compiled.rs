@@ -0,0 +1,325 @@
+//! A compact form of a place/transition net for simulation and analysis.
+//!
+//! Places and transitions get dense indices, starting at 0 in the order of
+//! [`PetriNet::places`] and [`PetriNet::transitions`]. The arcs are stored as sparse rows
+//! of weights (compressed sparse rows), so firing a transition only touches the places
+//! connected to it. Reference nodes are resolved and parallel arcs are added up.
+//! The compiled net is a snapshot, later changes of the net are not reflected.
+
+use crate::semantics::Marking;
+use crate::*;
+use std::collections::HashMap;
+
+/// A place/transition net with dense indices, see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct CompiledNet {
+    places: Vec<NodeRef>,
+    transitions: Vec<NodeRef>,
+    place_ids: Vec<String>,
+    transition_ids: Vec<String>,
+    /// indices of places and transitions by their handles and by the handles of their reference nodes
+    indices: HashMap<NodeRef, usize>,
+    /// places and transitions by the ids of themselves and their reference nodes
+    ids: HashMap<String, NodeRef>,
+    /// the places a transition consumes from
+    pre: Rows,
+    /// the places a transition produces on
+    post: Rows,
+    /// the transitions producing on a place
+    place_pre: Rows,
+    /// the transitions consuming from a place
+    place_post: Rows,
+    initial_marking: Marking,
+}
+
+/// Sparse rows of (column, weight), the entries of row i are at `offsets[i]..offsets[i + 1]`
+#[derive(Debug, Clone)]
+struct Rows {
+    offsets: Vec<usize>,
+    columns: Vec<usize>,
+    weights: Vec<usize>,
+}
+
+impl Rows {
+    /// Builds the rows from (row, column, weight) entries.
+    /// Entries with the same row and column are added up, None if a sum exceeds `usize::MAX`.
+    fn new(rows: usize, mut entries: Vec<(usize, usize, usize)>) -> Option<Self> {
+        entries.sort_unstable();
+        let mut offsets = vec![0; rows + 1];
+        let mut columns = Vec::with_capacity(entries.len());
+        let mut weights: Vec<usize> = Vec::with_capacity(entries.len());
+        let mut last = None;
+        for (row, column, weight) in entries {
+            if last == Some((row, column)) {
+                if let Some(total) = weights.last_mut() {
+                    *total = total.checked_add(weight)?;
+                }
+                continue;
+            }
+            last = Some((row, column));
+            offsets[row + 1] += 1;
+            columns.push(column);
+            weights.push(weight);
+        }
+        for row in 0..rows {
+            offsets[row + 1] += offsets[row];
+        }
+        Some(Rows {
+            offsets,
+            columns,
+            weights,
+        })
+    }
+
+    /// the same entries with rows and columns swapped
+    fn transposed(&self, rows: usize) -> Self {
+        let entries = self
+            .entries()
+            .map(|(row, column, weight)| (column, row, weight))
+            .collect();
+        Rows::new(rows, entries).expect("transposed entries are unique, nothing is added up")
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        (0..self.offsets.len() - 1).flat_map(move |row| {
+            self.row(row)
+                .map(move |(column, weight)| (row, column, weight))
+        })
+    }
+
+    fn row(&self, row: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let range = self.offsets[row]..self.offsets[row + 1];
+        self.columns[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.weights[range].iter().copied())
+    }
+}
+
+impl PetriNet {
+    /// Compiles the net for analysis, see [`CompiledNet`]
+    pub fn compile(&self) -> Result<CompiledNet> {
+        CompiledNet::new(self)
+    }
+}
+
+/// the index of a place or transition of the net being compiled
+fn index_of(indices: &HashMap<NodeRef, usize>, node: &NodeRef) -> Result<usize> {
+    indices.get(node).copied().ok_or_else(|| node.not_found())
+}
+
+/// the error for an arc endpoint with an id no node has
+fn missing_endpoint(id: &PNMLID, is_place: bool) -> PetriError {
+    let (handle, id, page) = (None, Some(id.0.clone()), None);
+    if is_place {
+        PetriError::PlaceNotFound { handle, id, page }
+    } else {
+        PetriError::TransitionNotFound { handle, id, page }
+    }
+}
+
+impl CompiledNet {
+    /// Compiles the net. Fails if a reference node or arc of the net cannot be resolved.
+    pub fn new(net: &PetriNet) -> Result<Self> {
+        let places: Vec<NodeRef> = net.places().collect();
+        let transitions: Vec<NodeRef> = net.transitions().collect();
+        let mut indices: HashMap<NodeRef, usize> = HashMap::new();
+        indices.extend(places.iter().enumerate().map(|(i, node)| (*node, i)));
+        indices.extend(transitions.iter().enumerate().map(|(i, node)| (*node, i)));
+        let place_ids = places
+            .iter()
+            .map(|node| node.id(net).map(String::from))
+            .collect::<Result<Vec<String>>>()?;
+        let transition_ids = transitions
+            .iter()
+            .map(|node| node.id(net).map(String::from))
+            .collect::<Result<Vec<String>>>()?;
+        let mut ids: HashMap<String, NodeRef> = HashMap::new();
+        ids.extend(place_ids.iter().cloned().zip(places.iter().copied()));
+        ids.extend(
+            transition_ids
+                .iter()
+                .cloned()
+                .zip(transitions.iter().copied()),
+        );
+        for (key, obj) in net.objects() {
+            if let pnml::Object::Node(node @ pnml::Node::PlaceRef(_))
+            | pnml::Object::Node(node @ pnml::Node::TransitionRef(_)) = &obj.object
+            {
+                let reference = NodeRef::new(key, node);
+                let resolved = net.resolve_reference(&reference)?;
+                let index = index_of(&indices, &resolved)?;
+                indices.insert(reference, index);
+                ids.insert(obj.id.0.clone(), resolved);
+            }
+        }
+        let mut pre = Vec::new();
+        let mut post = Vec::new();
+        // all arcs, PetriNet::arcs skips those with endpoints that do not exist
+        for (key, obj) in net.objects() {
+            let (source_id, sink_id) = match &obj.object {
+                pnml::Object::Arc(source, sink) => (source, sink),
+                _ => continue,
+            };
+            let compile = |e: PetriError| e.context(Operation::Compile, Some(&obj.id));
+            // a file may contain arcs the editing functions reject
+            let (source_ref, sink_ref) = match (net.node_by_id(source_id), net.node_by_id(sink_id))
+            {
+                (Some(source), Some(sink)) => (source, sink),
+                (None, sink) => {
+                    let is_place = !matches!(sink, Some(NodeRef::PlaceRef(_)));
+                    return Err(compile(missing_endpoint(source_id, is_place)));
+                }
+                (source, None) => {
+                    let is_place = matches!(source, Some(NodeRef::TransitionRef(_)));
+                    return Err(compile(missing_endpoint(sink_id, is_place)));
+                }
+            };
+            let source_node = net.resolve_reference(&source_ref).map_err(compile)?;
+            let sink_node = net.resolve_reference(&sink_ref).map_err(compile)?;
+            let source = index_of(&indices, &source_node).map_err(compile)?;
+            let sink = index_of(&indices, &sink_node).map_err(compile)?;
+            let weight = ArcRef(key).get_inscription(net).map_err(compile)?.get();
+            match (source_node, sink_node) {
+                (NodeRef::PlaceRef(_), NodeRef::TransitionRef(_)) => {
+                    pre.push((sink, source, weight))
+                }
+                (NodeRef::TransitionRef(_), NodeRef::PlaceRef(_)) => {
+                    post.push((source, sink, weight))
+                }
+                _ => {
+                    return Err(compile(PetriError::BipartitionViolation {
+                        source: source_id.0.clone(),
+                        target: sink_id.0.clone(),
+                    }))
+                }
+            }
+        }
+        let too_many_tokens =
+            || PetriError::invalid(None, "parallel arcs carry more tokens than can be counted");
+        let pre = Rows::new(transitions.len(), pre).ok_or_else(too_many_tokens)?;
+        let post = Rows::new(transitions.len(), post).ok_or_else(too_many_tokens)?;
+        let initial_marking = net.initial_marking()?;
+        Ok(CompiledNet {
+            place_pre: post.transposed(places.len()),
+            place_post: pre.transposed(places.len()),
+            pre,
+            post,
+            places,
+            transitions,
+            place_ids,
+            transition_ids,
+            indices,
+            ids,
+            initial_marking,
+        })
+    }
+
+    pub fn place_count(&self) -> usize {
+        self.places.len()
+    }
+
+    pub fn transition_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// the handle of the place with the index
+    pub fn place(&self, place: usize) -> NodeRef {
+        self.places[place]
+    }
+
+    /// the handle of the transition with the index
+    pub fn transition(&self, transition: usize) -> NodeRef {
+        self.transitions[transition]
+    }
+
+    /// the pnml id of the place with the index
+    pub fn place_id(&self, place: usize) -> &str {
+        &self.place_ids[place]
+    }
+
+    /// the pnml id of the transition with the index
+    pub fn transition_id(&self, transition: usize) -> &str {
+        &self.transition_ids[transition]
+    }
+
+    /// The index of the place, also for handles of its reference places.
+    /// None if the handle is no place of the compiled net.
+    pub fn place_index(&self, place: &NodeRef) -> Option<usize> {
+        match place {
+            NodeRef::PlaceRef(_) => self.indices.get(place).copied(),
+            NodeRef::TransitionRef(_) => None,
+        }
+    }
+
+    /// The index of the transition, also for handles of its reference transitions.
+    /// None if the handle is no transition of the compiled net.
+    pub fn transition_index(&self, transition: &NodeRef) -> Option<usize> {
+        match transition {
+            NodeRef::TransitionRef(_) => self.indices.get(transition).copied(),
+            NodeRef::PlaceRef(_) => None,
+        }
+    }
+
+    /// The index of the place with the pnml id (or the id of one of its reference places)
+    pub fn place_index_of_id(&self, id: &str) -> Option<usize> {
+        self.place_index(self.ids.get(id)?)
+    }
+
+    /// The index of the transition with the pnml id (or the id of one of its reference transitions)
+    pub fn transition_index_of_id(&self, id: &str) -> Option<usize> {
+        self.transition_index(self.ids.get(id)?)
+    }
+
+    /// (place, weight) for every place the transition consumes tokens from
+    pub fn pre(&self, transition: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pre.row(transition)
+    }
+
+    /// (place, weight) for every place the transition produces tokens on
+    pub fn post(&self, transition: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.post.row(transition)
+    }
+
+    /// (transition, weight) for every transition producing tokens on the place
+    pub fn place_pre(&self, place: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.place_pre.row(place)
+    }
+
+    /// (transition, weight) for every transition consuming tokens from the place
+    pub fn place_post(&self, place: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.place_post.row(place)
+    }
+
+    pub fn initial_marking(&self) -> &Marking {
+        &self.initial_marking
+    }
+
+    /// Tells if the transition can fire in the marking.
+    /// The marking must have one entry per place.
+    pub fn is_enabled(&self, transition: usize, marking: &Marking) -> bool {
+        self.pre(transition)
+            .all(|(place, weight)| marking[place] >= weight)
+    }
+
+    /// All transitions that can fire in the marking, in the order of their indices
+    pub fn enabled<'a>(&'a self, marking: &'a Marking) -> impl Iterator<Item = usize> + 'a {
+        (0..self.transitions.len()).filter(move |transition| self.is_enabled(*transition, marking))
+    }
+
+    /// The marking reached by firing the transition.
+    /// None if the transition is not enabled or a place would exceed `usize::MAX` tokens.
+    pub fn fire(&self, transition: usize, marking: &Marking) -> Option<Marking> {
+        if !self.is_enabled(transition, marking) {
+            return None;
+        }
+        let mut tokens = marking.tokens().to_vec();
+        for (place, weight) in self.pre(transition) {
+            tokens[place] -= weight;
+        }
+        for (place, weight) in self.post(transition) {
+            tokens[place] = tokens[place].checked_add(weight)?;
+        }
+        Some(Marking::new(tokens))
+    }
+}
@@ -0,0 +1,273 @@
+//! The coverability graph of a place/transition net after Karp and Miller.
+//!
+//! The reachability graph of an unbounded net is infinite. The coverability graph stays
+//! finite by replacing token counts that can grow without limit by ω: whenever a marking
+//! strictly covers a marking on the path from the initial marking to it, the firing
+//! sequence between them can be repeated, so the places that gained tokens get ω.
+//! The graph tells which places are bounded and gives a firing sequence proving that a
+//! net is unbounded.
+
+use crate::compiled::CompiledNet;
+use crate::reachability::Edge;
+use crate::semantics::Marking;
+use std::collections::HashMap;
+use std::ops::Index;
+
+/// The tokens on a place in an [`OmegaMarking`]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub enum Tokens {
+    Finite(usize),
+    /// arbitrary many tokens (ω), more than any finite number
+    Omega,
+}
+
+/// A marking where places may hold ω tokens, indexed like [`Marking`]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct OmegaMarking(Vec<Tokens>);
+
+impl OmegaMarking {
+    pub fn tokens(&self) -> &[Tokens] {
+        &self.0
+    }
+
+    /// true if every place of the marking holds at least as many tokens as in `other`
+    pub fn covers(&self, other: &OmegaMarking) -> bool {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .all(|(this, other)| this >= other)
+    }
+
+    fn has_omega(&self) -> bool {
+        self.0.contains(&Tokens::Omega)
+    }
+}
+
+impl From<&Marking> for OmegaMarking {
+    fn from(marking: &Marking) -> Self {
+        OmegaMarking(
+            marking
+                .tokens()
+                .iter()
+                .map(|n| Tokens::Finite(*n))
+                .collect(),
+        )
+    }
+}
+
+impl Index<usize> for OmegaMarking {
+    type Output = Tokens;
+
+    fn index(&self, place: usize) -> &Tokens {
+        &self.0[place]
+    }
+}
+
+/// A proof that a net is unbounded.
+/// Firing `prefix` from the initial marking and then `pump` any number of times is possible,
+/// every repetition of `pump` adds tokens to `places` and removes none.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UnboundednessWitness {
+    /// indices of the transitions fired from the initial marking
+    pub prefix: Vec<usize>,
+    /// indices of the transitions that can be repeated
+    pub pump: Vec<usize>,
+    /// indices of the places that gain tokens by the pump
+    pub places: Vec<usize>,
+}
+
+/// The coverability graph of a [`CompiledNet`], see the [module documentation](self).
+/// State 0 is the initial marking.
+#[derive(Debug, Clone)]
+pub struct CoverabilityGraph {
+    markings: Vec<OmegaMarking>,
+    edges: Vec<Vec<Edge>>,
+    /// the state and transition each state was found from, None for the initial state
+    parents: Vec<Option<(usize, usize)>>,
+    witness: Option<UnboundednessWitness>,
+}
+
+impl CompiledNet {
+    /// Builds the coverability graph starting at the initial marking.
+    /// The graph is always finite, but may be large for nets with many reachable markings.
+    pub fn coverability_graph(&self) -> CoverabilityGraph {
+        CoverabilityGraph::new(self, self.initial_marking())
+    }
+
+    fn is_omega_enabled(&self, transition: usize, marking: &OmegaMarking) -> bool {
+        self.pre(transition)
+            .all(|(place, weight)| marking[place] >= Tokens::Finite(weight))
+    }
+
+    /// Fires the enabled transition, ω stays ω.
+    /// A place that would exceed `usize::MAX` tokens gets ω.
+    fn fire_omega(&self, transition: usize, marking: &OmegaMarking) -> OmegaMarking {
+        let mut tokens = marking.0.clone();
+        for (place, weight) in self.pre(transition) {
+            if let Tokens::Finite(n) = &mut tokens[place] {
+                *n -= weight;
+            }
+        }
+        for (place, weight) in self.post(transition) {
+            if let Tokens::Finite(n) = tokens[place] {
+                tokens[place] = n.checked_add(weight).map_or(Tokens::Omega, Tokens::Finite);
+            }
+        }
+        OmegaMarking(tokens)
+    }
+}
+
+impl CoverabilityGraph {
+    /// Builds the coverability graph starting at `initial`, which becomes state 0.
+    /// The marking must have one entry per place of the net.
+    pub fn new(net: &CompiledNet, initial: &Marking) -> Self {
+        let mut graph = CoverabilityGraph {
+            markings: vec![initial.into()],
+            edges: Vec::new(),
+            parents: vec![None],
+            witness: None,
+        };
+        let mut states: HashMap<OmegaMarking, usize> = HashMap::new();
+        states.insert(graph.markings[0].clone(), 0);
+        while graph.edges.len() < graph.markings.len() {
+            let state = graph.edges.len();
+            let marking = graph.markings[state].clone();
+            let mut edges = Vec::new();
+            for transition in 0..net.transition_count() {
+                if !net.is_omega_enabled(transition, &marking) {
+                    continue;
+                }
+                let fired = net.fire_omega(transition, &marking);
+                let next = graph.accelerate(state, transition, &fired);
+                let target = match states.get(&next) {
+                    Some(target) => *target,
+                    None => {
+                        let target = graph.markings.len();
+                        states.insert(next.clone(), target);
+                        graph.markings.push(next);
+                        graph.parents.push(Some((state, transition)));
+                        target
+                    }
+                };
+                edges.push(Edge { transition, target });
+            }
+            graph.edges.push(edges);
+        }
+        graph
+    }
+
+    /// Sets ω on the places where the marking reached by firing `transition` in `state`
+    /// exceeds a marking on the path to it. Records the first witness of unboundedness.
+    fn accelerate(
+        &mut self,
+        state: usize,
+        transition: usize,
+        fired: &OmegaMarking,
+    ) -> OmegaMarking {
+        let mut next = fired.clone();
+        let mut ancestor = Some(state);
+        while let Some(current) = ancestor {
+            let marking = &self.markings[current];
+            if next.covers(marking) && next != *marking {
+                for (tokens, old) in next.0.iter_mut().zip(&marking.0) {
+                    if *tokens > *old {
+                        *tokens = Tokens::Omega;
+                    }
+                }
+            }
+            ancestor = self.parents[current].map(|(parent, _)| parent);
+        }
+        // all markings on the path are finite, so the path is a real firing sequence
+        if self.witness.is_none() && next.has_omega() && !self.markings[state].has_omega() {
+            self.witness = self.find_witness(state, transition, fired);
+        }
+        next
+    }
+
+    fn find_witness(
+        &self,
+        state: usize,
+        transition: usize,
+        fired: &OmegaMarking,
+    ) -> Option<UnboundednessWitness> {
+        let mut path = self.path(state);
+        path.push(transition);
+        let mut ancestors = vec![state];
+        while let Some((parent, _)) = self.parents[*ancestors.last()?] {
+            ancestors.push(parent);
+        }
+        // the ancestor closest to the initial marking, its path is the prefix
+        let (depth, ancestor) = ancestors.iter().rev().enumerate().find(|(_, ancestor)| {
+            let marking = &self.markings[**ancestor];
+            fired.covers(marking) && fired != marking
+        })?;
+        let marking = &self.markings[*ancestor];
+        let places = (0..fired.0.len())
+            .filter(|place| fired[*place] > marking[*place])
+            .collect();
+        let pump = path.split_off(depth);
+        Some(UnboundednessWitness {
+            prefix: path,
+            pump,
+            places,
+        })
+    }
+
+    /// the transitions fired on the path from the initial marking to the state in the tree of first visits
+    fn path(&self, mut state: usize) -> Vec<usize> {
+        let mut path = Vec::new();
+        while let Some((parent, transition)) = self.parents[state] {
+            path.push(transition);
+            state = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.markings.len()
+    }
+
+    pub fn marking(&self, state: usize) -> &OmegaMarking {
+        &self.markings[state]
+    }
+
+    /// The edges leaving the state, ordered by transition.
+    /// Empty for states that are not in the graph.
+    pub fn successors(&self, state: usize) -> &[Edge] {
+        self.edges.get(state).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The maximal number of tokens on the place in any reachable marking,
+    /// None if the place is unbounded
+    pub fn place_bound(&self, place: usize) -> Option<usize> {
+        let mut bound = 0;
+        for marking in &self.markings {
+            match marking[place] {
+                Tokens::Finite(n) => bound = bound.max(n),
+                Tokens::Omega => return None,
+            }
+        }
+        Some(bound)
+    }
+
+    /// The smallest k such that the net is k-bounded, None if it is unbounded
+    pub fn bound(&self) -> Option<usize> {
+        let places = self.markings.first().map_or(0, |marking| marking.0.len());
+        (0..places).try_fold(0, |bound, place| Some(self.place_bound(place)?.max(bound)))
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        !self.markings.iter().any(OmegaMarking::has_omega)
+    }
+
+    /// true if no place can hold more than k tokens
+    pub fn is_k_bounded(&self, k: usize) -> bool {
+        self.bound().is_some_and(|bound| bound <= k)
+    }
+
+    /// A firing sequence that proves that the net is unbounded, None if it is bounded
+    pub fn unboundedness_witness(&self) -> Option<&UnboundednessWitness> {
+        self.witness.as_ref()
+    }
+}
@@ -0,0 +1,147 @@
+//! Search for dead markings, markings in which no transition can fire.
+//!
+//! The search explores the reachable markings from the initial marking and stops at the
+//! first dead one. It returns the firing sequence leading there, with the handles, ids
+//! and names of the transitions, so it can be printed or replayed with [`PetriNet::fire`].
+
+use crate::compiled::CompiledNet;
+use crate::reachability::{state_size, Limit, Limits};
+use crate::semantics::Marking;
+use crate::*;
+use std::collections::{HashMap, VecDeque};
+use std::mem::size_of;
+
+/// The order in which the markings are explored
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SearchStrategy {
+    /// Explores the markings by their distance from the initial marking,
+    /// the trace to a dead marking is as short as possible
+    BreadthFirst,
+    /// Follows each firing sequence as far as possible before trying the next one.
+    /// Needs less memory for deep state spaces, the trace may be longer than necessary.
+    DepthFirst,
+}
+
+/// A transition fired in a trace
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TraceStep {
+    pub transition: NodeRef,
+    pub id: String,
+    pub name: Option<String>,
+}
+
+/// A dead marking with the firing sequence leading to it from the initial marking
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Deadlock {
+    pub trace: Vec<TraceStep>,
+    pub marking: Marking,
+}
+
+/// The result of a deadlock search
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum DeadlockVerdict {
+    Deadlock(Deadlock),
+    /// every reachable marking enables a transition
+    DeadlockFree,
+    /// the search stopped at the limit before it found a dead marking
+    Unknown(Limit),
+}
+
+impl PetriNet {
+    /// Searches the reachable markings for a dead marking within the limits
+    pub fn find_deadlock(
+        &self,
+        strategy: SearchStrategy,
+        limits: Limits,
+    ) -> Result<DeadlockVerdict> {
+        let net = self.compile()?;
+        let (trace, marking) = match search(&net, strategy, limits) {
+            Ok(Some(deadlock)) => deadlock,
+            Ok(None) => return Ok(DeadlockVerdict::DeadlockFree),
+            Err(limit) => return Ok(DeadlockVerdict::Unknown(limit)),
+        };
+        let trace = trace
+            .into_iter()
+            .map(|index| {
+                let transition = net.transition(index);
+                Ok(TraceStep {
+                    transition,
+                    id: net.transition_id(index).into(),
+                    name: transition.get_name(self)?.map(String::from),
+                })
+            })
+            .collect::<Result<Vec<TraceStep>>>()?;
+        Ok(DeadlockVerdict::Deadlock(Deadlock { trace, marking }))
+    }
+}
+
+/// The transitions leading to the first dead marking found and the marking,
+/// None if there is no dead marking
+fn search(
+    net: &CompiledNet,
+    strategy: SearchStrategy,
+    limits: Limits,
+) -> std::result::Result<Option<(Vec<usize>, Marking)>, Limit> {
+    let state_size = state_size(net) + size_of::<Option<(usize, usize)>>();
+    let mut markings = vec![net.initial_marking().clone()];
+    // the state and transition each state was found from
+    let mut parents: Vec<Option<(usize, usize)>> = vec![None];
+    let mut states: HashMap<Marking, usize> = HashMap::new();
+    states.insert(markings[0].clone(), 0);
+    let mut pending = VecDeque::from(vec![0]);
+    let mut memory = state_size;
+    let is_dead = |marking: &Marking| net.enabled(marking).next().is_none();
+    if is_dead(&markings[0]) {
+        return Ok(Some((Vec::new(), markings[0].clone())));
+    }
+    loop {
+        let state = match strategy {
+            SearchStrategy::BreadthFirst => pending.pop_front(),
+            SearchStrategy::DepthFirst => pending.pop_back(),
+        };
+        let state = match state {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        let marking = markings[state].clone();
+        for transition in net.enabled(&marking) {
+            let next = net.fire(transition, &marking).ok_or(Limit::Tokens)?;
+            if states.contains_key(&next) {
+                continue;
+            }
+            // markings are checked when they are found, so a limit reached later on
+            // does not hide a dead marking that was already found
+            if is_dead(&next) {
+                let mut trace = trace_to(&parents, state);
+                trace.push(transition);
+                return Ok(Some((trace, next)));
+            }
+            if limits.max_states.is_some_and(|max| markings.len() >= max) {
+                return Err(Limit::States);
+            }
+            if limits
+                .max_memory
+                .is_some_and(|max| memory + state_size > max)
+            {
+                return Err(Limit::Memory);
+            }
+            memory += state_size;
+            states.insert(next.clone(), markings.len());
+            pending.push_back(markings.len());
+            markings.push(next);
+            parents.push(Some((state, transition)));
+        }
+    }
+}
+
+/// the transitions fired from the initial marking to the state
+fn trace_to(parents: &[Option<(usize, usize)>], state: usize) -> Vec<usize> {
+    let mut trace = Vec::new();
+    let mut current = state;
+    while let Some((parent, transition)) = parents[current] {
+        trace.push(transition);
+        current = parent;
+    }
+    trace.reverse();
+    trace
+}
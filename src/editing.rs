@@ -0,0 +1,218 @@
+//! Removing and moving objects of a net, and flattening its pages.
+//!
+//! Handles of removed objects are reported as not found afterwards,
+//! the handles of all other objects stay valid.
+
+use crate::arena::Key;
+use crate::pnml::*;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+impl PetriNet {
+    /// Removes the place, transition or reference node.
+    /// All arcs connected to it and all reference nodes linking to it are removed as well.
+    pub fn remove_node(&mut self, node: &NodeRef) -> Result<()> {
+        let id = self
+            .get_node_obj(node)
+            .map_err(|e| e.context(Operation::RemoveNode, None))?
+            .id
+            .clone();
+        let mut nodes = HashSet::new();
+        nodes.insert(id.0);
+        self.remove_cascading(nodes)
+    }
+
+    pub fn remove_arc(&mut self, arc: &ArcRef) -> Result<()> {
+        self.get_arc_obj(arc)
+            .map_err(|e| e.context(Operation::RemoveArc, None))?;
+        self.remove_object(arc.0)
+    }
+
+    /// Removes the page with its sub pages and all objects on them.
+    /// Arcs on other pages connected to the removed nodes and reference nodes linking
+    /// to them are removed as well.
+    pub fn remove_page(&mut self, page: &PageRef) -> Result<()> {
+        self.get_page(page)
+            .map_err(|e| e.context(Operation::RemovePage, None))?;
+        let mut pages = vec![*page];
+        let mut i = 0;
+        while i < pages.len() {
+            pages.extend(pages[i].sub_pages(self)?);
+            i += 1;
+        }
+        let mut nodes = HashSet::new();
+        for (_, obj) in self.objects() {
+            if obj.page.is_some_and(|key| pages.contains(&PageRef(key)))
+                && matches!(obj.object, Object::Node(_))
+            {
+                nodes.insert(obj.id.0.clone());
+            }
+        }
+        self.remove_cascading(nodes)?;
+        // the remaining objects are arcs between nodes of other pages
+        let arcs: Vec<Key> = self
+            .objects()
+            .into_iter()
+            .filter(|(_, obj)| obj.page.is_some_and(|key| pages.contains(&PageRef(key))))
+            .map(|(key, _)| key)
+            .collect();
+        for key in arcs {
+            self.remove_object(key)?;
+        }
+        // children first, so their parents still exist
+        for page in pages.iter().rev() {
+            self.remove_object(page.0)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the node to another page.
+    ///
+    /// The node keeps its id and its handle, arcs and reference nodes stay connected to it.
+    pub fn move_node(&mut self, node: &NodeRef, target_page: &PageRef) -> Result<NodeRef> {
+        let move_node = |e: PetriError| e.context(Operation::MoveNode, None);
+        self.get_page(target_page).map_err(move_node)?;
+        let obj = self.get_node_obj_mut(node).map_err(move_node)?;
+        let source_page = obj.page.replace(target_page.0);
+        if let Some(source_page) = source_page {
+            let page = PetriNet::obj_to_page_mut(self.get_page_mut(&PageRef(source_page))?)?;
+            page.objects.retain(|key| *key != node.key());
+        }
+        PetriNet::obj_to_page_mut(self.get_page_mut(target_page)?)?
+            .objects
+            .push(node.key());
+        Ok(*node)
+    }
+
+    /// Merges all (sub) pages into the first page of the net and replaces every reference
+    /// node by the place or transition it links to, so the net consists of one page
+    /// without references.
+    ///
+    /// Arcs of reference nodes are connected to the linked nodes instead. Places,
+    /// transitions and arcs keep their handles and ids. The other pages and the reference
+    /// nodes are removed together with their names, graphics and labels.
+    /// Returns the new handle of every page, node and arc the net had before.
+    /// Nothing is changed if a reference node cannot be resolved.
+    pub fn flatten(&mut self) -> Result<HashMap<Handle, Handle>> {
+        let net_id = self.id.clone();
+        let flatten = |e: PetriError| e.context(Operation::Flatten, Some(&net_id));
+        let target = match self.pages.first() {
+            Some(key) => PageRef(*key),
+            None => return Ok(HashMap::new()),
+        };
+        let mut mapping = HashMap::new();
+        let pages = self.all_pages();
+        for page in &pages {
+            mapping.insert(Handle::Page(*page), Handle::Page(target));
+        }
+        // the places, transitions and arcs in the order of their pages
+        let mut kept = Vec::new();
+        let mut references = Vec::new();
+        let mut arcs = Vec::new();
+        for (key, obj) in self.objects() {
+            match &obj.object {
+                Object::Node(node) => {
+                    let node = NodeRef::new(key, node);
+                    let resolved = self.resolve_reference(&node).map_err(flatten)?;
+                    mapping.insert(Handle::Node(node), Handle::Node(resolved));
+                    if resolved == node {
+                        kept.push(key);
+                    } else {
+                        references.push(key);
+                    }
+                }
+                Object::Arc(source, sink) => {
+                    let resolve = |id: &PNMLID| match self.node_by_id(id) {
+                        Some(node) => Ok(self
+                            .get_node_obj(&self.resolve_reference(&node)?)?
+                            .id
+                            .clone()),
+                        // arcs of read documents can be dangling, they are kept as they are
+                        None => Ok(id.clone()),
+                    };
+                    let source = resolve(source).map_err(flatten)?;
+                    let sink = resolve(sink).map_err(flatten)?;
+                    arcs.push((key, source, sink));
+                    mapping.insert(Handle::Arc(ArcRef(key)), Handle::Arc(ArcRef(key)));
+                    kept.push(key);
+                }
+                Object::Page(_) => {}
+            }
+        }
+        for (key, source, sink) in arcs {
+            if let Some(arc) = self.objects.get_mut(key) {
+                arc.object = Object::Arc(source, sink);
+            }
+        }
+        for key in references {
+            self.remove_object(key)?;
+        }
+        for key in &kept {
+            if let Some(obj) = self.objects.get_mut(*key) {
+                obj.page = Some(target.0);
+            }
+        }
+        let page = PetriNet::obj_to_page_mut(self.get_page_mut(&target)?)?;
+        page.objects = kept;
+        page.sub_pages.clear();
+        // children first, so their parents still exist
+        for page in pages.iter().rev().filter(|page| **page != target) {
+            self.remove_object(page.0)?;
+        }
+        Ok(mapping)
+    }
+
+    /// removes the nodes with the ids, the reference nodes linking to them
+    /// and all arcs connected to any of them
+    fn remove_cascading(&mut self, mut nodes: HashSet<String>) -> Result<()> {
+        let objects = self.objects();
+        // reference nodes can link to reference nodes, so repeat until nothing is added
+        loop {
+            let count = nodes.len();
+            for (_, obj) in &objects {
+                if let Object::Node(Node::PlaceRef(target))
+                | Object::Node(Node::TransitionRef(target)) = &obj.object
+                {
+                    if nodes.contains(&target.0) {
+                        nodes.insert(obj.id.0.clone());
+                    }
+                }
+            }
+            if nodes.len() == count {
+                break;
+            }
+        }
+        let removed: Vec<Key> = objects
+            .into_iter()
+            .filter(|(_, obj)| match &obj.object {
+                Object::Node(_) => nodes.contains(&obj.id.0),
+                Object::Arc(source, sink) => nodes.contains(&source.0) || nodes.contains(&sink.0),
+                Object::Page(_) => false,
+            })
+            .map(|(key, _)| key)
+            .collect();
+        for key in removed {
+            self.remove_object(key)?;
+        }
+        Ok(())
+    }
+
+    /// takes the object out of the net and out of the page it is on
+    fn remove_object(&mut self, key: Key) -> Result<()> {
+        let obj = match self.objects.remove(key) {
+            Some(obj) => obj,
+            None => return Ok(()),
+        };
+        match obj.page {
+            Some(parent) => {
+                let parent = PetriNet::obj_to_page_mut(self.get_page_mut(&PageRef(parent))?)?;
+                parent.objects.retain(|other| *other != key);
+                parent.sub_pages.retain(|other| *other != key);
+            }
+            None => self.pages.retain(|other| *other != key),
+        }
+        self.ids.unregister(&obj.id);
+        self.index.remove(&obj.id.0);
+        Ok(())
+    }
+}
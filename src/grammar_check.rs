@@ -0,0 +1,108 @@
+//! Structural checks of documents against RELAX NG grammars modelled on the pnml standard.
+//!
+//! Grammars for the core model and for place/transition nets are embedded in the crate,
+//! so the structure of a document can be checked without network access before it is
+//! handed to other tools. Violations are reported with the path of the offending element,
+//! e.g. `/pnml/net[1]/page[1]/place[2]/initialMarking[1]/text[1]`.
+//!
+//! This is no conformance check: the embedded files in `grammars/` are not the official
+//! files published at `http://www.pnml.org/version-2009/grammar/`, so a document without
+//! violations may still be rejected by the official grammars. They are written after the
+//! grammars of ISO/IEC 15909-2 and keep their define names and structure, with these
+//! differences:
+//!
+//! * `conventions.rng` only defines `nonnegativeintegerlabel.content` and
+//!   `positiveintegerlabel.content`, the labels `ptnet.rng` refers to. The conventions of
+//!   the other net types are left out, so only the core model and place/transition nets
+//!   can be checked.
+//! * The `a:documentation` annotations of the official files are left out, they do not
+//!   change the accepted documents.
+//! * Includes are resolved by file name against the embedded files instead of being
+//!   downloaded from their URL.
+//!
+//! The grammars are compiled once per thread, [`Grammar::pt_net`] and
+//! [`Grammar::core_model`] return clones that share the compiled patterns.
+
+use crate::relaxng::{Schema, XmlElement};
+use crate::*;
+use std::io::Read;
+
+/// the embedded grammars by the file name used in their includes
+const GRAMMARS: [(&str, &str); 3] = [
+    (
+        "pnmlcoremodel.rng",
+        include_str!("../grammars/pnmlcoremodel.rng"),
+    ),
+    (
+        "conventions.rng",
+        include_str!("../grammars/conventions.rng"),
+    ),
+    ("ptnet.rng", include_str!("../grammars/ptnet.rng")),
+];
+
+thread_local! {
+    static PT_NET: Grammar = Grammar::embedded("ptnet.rng");
+    static CORE_MODEL: Grammar = Grammar::embedded("pnmlcoremodel.rng");
+}
+
+/// A compiled grammar of a net type, an approximation of the official one
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    schema: Schema,
+}
+
+/// A part of a document that does not conform to the grammar
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Violation {
+    /// path of the element (or attribute) with the problem, starting at the root element
+    pub path: String,
+    pub message: String,
+}
+
+impl Violation {
+    pub(crate) fn new(path: &str, message: impl Into<String>) -> Self {
+        Violation {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Grammar {
+    /// The embedded grammar of place/transition nets, the net type written by this crate
+    pub fn pt_net() -> Self {
+        PT_NET.with(Grammar::clone)
+    }
+
+    /// The embedded grammar of the core model, which accepts any net type but no labels
+    pub fn core_model() -> Self {
+        CORE_MODEL.with(Grammar::clone)
+    }
+
+    fn embedded(start: &str) -> Self {
+        let schema = Schema::compile(&GRAMMARS, start).expect("the embedded grammars are valid");
+        Grammar { schema }
+    }
+
+    /// Checks a pnml document given as string, see [`Grammar::check_reader`]
+    pub fn check_xml(&self, xml: &str) -> Result<Vec<Violation>> {
+        self.check_reader(xml.as_bytes())
+    }
+
+    /// Checks a pnml document from a byte source like an opened `.pnml` file.
+    /// Returns all violations of the grammar, an empty list if the document fits it.
+    /// Fails only if the source is no well-formed xml.
+    pub fn check_reader<R: Read>(&self, source: R) -> Result<Vec<Violation>> {
+        Ok(self.schema.validate(&XmlElement::parse(source)?))
+    }
+}
+
+impl PNMLDocument {
+    /// Checks the written document against the embedded grammar of place/transition nets,
+    /// see the [module documentation](crate::grammar_check) for how far that goes.
+    /// Labels added with `add_label` are not part of the grammar and reported as violations.
+    pub fn check_structure(&self) -> Result<Vec<Violation>> {
+        let xml = self.to_xml()?;
+        PT_NET.with(|grammar| grammar.check_xml(&xml))
+    }
+}
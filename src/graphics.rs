@@ -0,0 +1,271 @@
+//! Graphical information of the pnml core model.
+//!
+//! Nodes (and pages) have a position and optionally a dimension, fill and line style.
+//! Arcs can have intermediate waypoints. Labels like names or markings (annotations)
+//! are placed with an offset relative to the object they belong to.
+
+use crate::pnml::*;
+use crate::*;
+
+/// A point (position, offset) or a size (dimension) in the plane
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct Coordinate {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum GradientRotation {
+    Vertical,
+    Horizontal,
+    Diagonal,
+}
+
+/// Interior of a node or annotation
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Fill {
+    /// a css2 color
+    pub color: Option<String>,
+    pub gradient_color: Option<String>,
+    pub gradient_rotation: Option<GradientRotation>,
+    /// uri of an image
+    pub image: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum LineShape {
+    Line,
+    Curve,
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum LineStyle {
+    Solid,
+    Dash,
+    Dot,
+}
+
+/// Outline of a node or annotation and the line of an arc
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Line {
+    pub shape: Option<LineShape>,
+    /// a css2 color
+    pub color: Option<String>,
+    pub width: Option<f64>,
+    pub style: Option<LineStyle>,
+}
+
+/// Font of an annotation. The values are css2 font properties
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Font {
+    pub family: Option<String>,
+    pub style: Option<String>,
+    pub weight: Option<String>,
+    pub size: Option<String>,
+    pub decoration: Option<String>,
+    pub align: Option<String>,
+    pub rotation: Option<f64>,
+}
+
+/// Graphics of places, transitions, reference nodes and pages
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct NodeGraphics {
+    /// position of the center of the node
+    pub position: Coordinate,
+    pub dimension: Option<Coordinate>,
+    pub fill: Option<Fill>,
+    pub line: Option<Line>,
+}
+
+/// Graphics of arcs
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct ArcGraphics {
+    /// intermediate points the arc is drawn through between source and target
+    pub positions: Vec<Coordinate>,
+    pub line: Option<Line>,
+}
+
+/// Graphics of labels like names, markings and inscriptions
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct AnnotationGraphics {
+    /// offset relative to the object the label belongs to
+    pub offset: Coordinate,
+    pub fill: Option<Fill>,
+    pub line: Option<Line>,
+    pub font: Option<Font>,
+}
+
+impl ObjectBase {
+    /// the node graphics of this object, initialized if there where none
+    pub(crate) fn node_graphics_mut(&mut self) -> Result<&mut NodeGraphics> {
+        match self
+            .graphics
+            .get_or_insert_with(|| Graphics::Node(NodeGraphics::default()))
+        {
+            Graphics::Node(graphics) => Ok(graphics),
+            Graphics::Arc(_) => Err(PetriError::corrupted(
+                Some(&self.id),
+                "node with arc graphics",
+            )),
+        }
+    }
+
+    /// the arc graphics of this object, initialized if there where none
+    pub(crate) fn arc_graphics_mut(&mut self) -> Result<&mut ArcGraphics> {
+        match self
+            .graphics
+            .get_or_insert_with(|| Graphics::Arc(ArcGraphics::default()))
+        {
+            Graphics::Arc(graphics) => Ok(graphics),
+            Graphics::Node(_) => Err(PetriError::corrupted(
+                Some(&self.id),
+                "arc with node graphics",
+            )),
+        }
+    }
+
+    /// sets the offset of the label that matches the predicate
+    fn label_offset(&mut self, is_label: fn(&Label) -> bool, offset: Coordinate) -> Result<()> {
+        let id = self.id.clone();
+        let label = self
+            .labels
+            .iter_mut()
+            .flatten()
+            .find(|label| is_label(&label.label))
+            .ok_or_else(|| PetriError::invalid(Some(&id), "the label is not set"))?;
+        label
+            .graphics
+            .get_or_insert_with(AnnotationGraphics::default)
+            .offset = offset;
+        Ok(())
+    }
+
+    fn name_offset(&mut self, offset: Coordinate) -> Result<()> {
+        if self.name.text.is_none() {
+            return Err(PetriError::invalid(Some(&self.id), "the name is not set"));
+        }
+        self.name
+            .graphics
+            .get_or_insert_with(AnnotationGraphics::default)
+            .offset = offset;
+        Ok(())
+    }
+}
+
+impl NodeRef {
+    /// sets the position of the center of the node
+    pub fn position(&mut self, net: &mut PetriNet, x: f64, y: f64) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?.node_graphics_mut()?.position = Coordinate { x, y };
+        Ok(self)
+    }
+
+    /// sets width and height of the node
+    pub fn dimension(&mut self, net: &mut PetriNet, width: f64, height: f64) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?.node_graphics_mut()?.dimension = Some(Coordinate {
+            x: width,
+            y: height,
+        });
+        Ok(self)
+    }
+
+    pub fn fill(&mut self, net: &mut PetriNet, fill: Fill) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?.node_graphics_mut()?.fill = Some(fill);
+        Ok(self)
+    }
+
+    pub fn line(&mut self, net: &mut PetriNet, line: Line) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?.node_graphics_mut()?.line = Some(line);
+        Ok(self)
+    }
+
+    /// sets the offset of the name relative to the node. The name has to be set before.
+    pub fn name_offset(&mut self, net: &mut PetriNet, x: f64, y: f64) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?
+            .name_offset(Coordinate { x, y })?;
+        Ok(self)
+    }
+
+    /// sets the offset of the marking relative to the place. The marking has to be set before.
+    pub fn initial_marking_offset(
+        &mut self,
+        net: &mut PetriNet,
+        x: f64,
+        y: f64,
+    ) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?.label_offset(
+            |label| matches!(label, Label::PTMarking(_)),
+            Coordinate { x, y },
+        )?;
+        Ok(self)
+    }
+
+    pub fn get_graphics<'a>(&self, net: &'a PetriNet) -> Result<Option<&'a NodeGraphics>> {
+        match &net.get_node_obj(self)?.graphics {
+            Some(Graphics::Node(graphics)) => Ok(Some(graphics)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn get_name_graphics<'a>(
+        &self,
+        net: &'a PetriNet,
+    ) -> Result<Option<&'a AnnotationGraphics>> {
+        Ok(net.get_node_obj(self)?.name.graphics.as_ref())
+    }
+}
+
+impl ArcRef {
+    /// sets the intermediate points the arc is drawn through
+    pub fn waypoints(
+        &mut self,
+        net: &mut PetriNet,
+        positions: Vec<Coordinate>,
+    ) -> Result<&mut Self> {
+        net.get_arc_obj_mut(self)?.arc_graphics_mut()?.positions = positions;
+        Ok(self)
+    }
+
+    /// appends an intermediate point to the arc
+    pub fn add_waypoint(&mut self, net: &mut PetriNet, x: f64, y: f64) -> Result<&mut Self> {
+        net.get_arc_obj_mut(self)?
+            .arc_graphics_mut()?
+            .positions
+            .push(Coordinate { x, y });
+        Ok(self)
+    }
+
+    pub fn line(&mut self, net: &mut PetriNet, line: Line) -> Result<&mut Self> {
+        net.get_arc_obj_mut(self)?.arc_graphics_mut()?.line = Some(line);
+        Ok(self)
+    }
+
+    /// sets the offset of the name relative to the arc. The name has to be set before.
+    pub fn name_offset(&mut self, net: &mut PetriNet, x: f64, y: f64) -> Result<&mut Self> {
+        net.get_arc_obj_mut(self)?
+            .name_offset(Coordinate { x, y })?;
+        Ok(self)
+    }
+
+    /// sets the offset of the inscription relative to the arc. The inscription has to be set before.
+    pub fn inscription_offset(&mut self, net: &mut PetriNet, x: f64, y: f64) -> Result<&mut Self> {
+        net.get_arc_obj_mut(self)?.label_offset(
+            |label| matches!(label, Label::PTAnnotation(_)),
+            Coordinate { x, y },
+        )?;
+        Ok(self)
+    }
+
+    pub fn get_graphics<'a>(&self, net: &'a PetriNet) -> Result<Option<&'a ArcGraphics>> {
+        match &net.get_arc_obj(self)?.graphics {
+            Some(Graphics::Arc(graphics)) => Ok(Some(graphics)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn get_name_graphics<'a>(
+        &self,
+        net: &'a PetriNet,
+    ) -> Result<Option<&'a AnnotationGraphics>> {
+        Ok(net.get_arc_obj(self)?.name.graphics.as_ref())
+    }
+}
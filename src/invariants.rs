@@ -0,0 +1,227 @@
+//! Place and transition semiflows (invariants) of a place/transition net.
+//!
+//! A P-semiflow is a weighting y ≥ 0 of the places with y·C = 0 for the incidence matrix C:
+//! the weighted sum of tokens on its places is the same in every reachable marking.
+//! A T-semiflow is a weighting x ≥ 0 of the transitions with C·x = 0: firing each transition
+//! as often as its weight leads back to the same marking.
+//!
+//! The semiflows are computed with the Farkas algorithm, removing combinations without
+//! minimal support after each step (Martínez and Silva). The result is the set of minimal
+//! semiflows, every semiflow of the net is a non-negative combination of them. The
+//! arithmetic is exact, nets where the numbers would overflow are reported as error.
+
+use crate::compiled::CompiledNet;
+use crate::*;
+use std::convert::TryFrom;
+
+/// A place or transition with its weight in a [`Semiflow`]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct WeightedNode {
+    pub node: NodeRef,
+    pub id: String,
+    /// the index of the node in the [`CompiledNet`]
+    pub index: usize,
+    pub weight: usize,
+}
+
+/// A minimal semiflow, only the nodes with a weight above zero are listed
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Semiflow {
+    entries: Vec<WeightedNode>,
+}
+
+impl Semiflow {
+    /// the nodes of the semiflow, ordered by index
+    pub fn entries(&self) -> &[WeightedNode] {
+        &self.entries
+    }
+
+    /// the weight of the node, 0 if it is not part of the semiflow
+    pub fn weight(&self, node: &NodeRef) -> usize {
+        self.entries
+            .iter()
+            .find(|entry| entry.node == *node)
+            .map_or(0, |entry| entry.weight)
+    }
+
+    /// the weight of the node with the id, 0 if it is not part of the semiflow
+    pub fn weight_of_id(&self, id: &str) -> usize {
+        self.entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map_or(0, |entry| entry.weight)
+    }
+}
+
+impl PetriNet {
+    /// The minimal P-semiflows of the net, see the [module documentation](crate::invariants)
+    pub fn p_semiflows(&self) -> Result<Vec<Semiflow>> {
+        self.compile()?.p_semiflows()
+    }
+
+    /// The minimal T-semiflows of the net, see the [module documentation](crate::invariants)
+    pub fn t_semiflows(&self) -> Result<Vec<Semiflow>> {
+        self.compile()?.t_semiflows()
+    }
+}
+
+impl CompiledNet {
+    /// The minimal P-semiflows of the net, see the [module documentation](crate::invariants)
+    pub fn p_semiflows(&self) -> Result<Vec<Semiflow>> {
+        // one row per place
+        let matrix = self.incidence_matrices()?.incidence().to_dense();
+        let semiflows = farkas(&matrix, self.transition_count())?;
+        Ok(semiflows
+            .into_iter()
+            .map(|weights| self.semiflow(weights, |net, p| (net.place(p), net.place_id(p))))
+            .collect())
+    }
+
+    /// The minimal T-semiflows of the net, see the [module documentation](crate::invariants)
+    pub fn t_semiflows(&self) -> Result<Vec<Semiflow>> {
+        let incidence = self.incidence_matrices()?.incidence().to_dense();
+        // one row per transition
+        let matrix: Vec<Vec<i64>> = (0..self.transition_count())
+            .map(|t| incidence.iter().map(|row| row[t]).collect())
+            .collect();
+        let semiflows = farkas(&matrix, self.place_count())?;
+        Ok(semiflows
+            .into_iter()
+            .map(|weights| {
+                self.semiflow(weights, |net, t| (net.transition(t), net.transition_id(t)))
+            })
+            .collect())
+    }
+
+    fn semiflow(
+        &self,
+        weights: Vec<usize>,
+        node: impl Fn(&CompiledNet, usize) -> (NodeRef, &str),
+    ) -> Semiflow {
+        let entries = weights
+            .into_iter()
+            .enumerate()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(index, weight)| {
+                let (node, id) = node(self, index);
+                WeightedNode {
+                    node,
+                    id: id.into(),
+                    index,
+                    weight,
+                }
+            })
+            .collect();
+        Semiflow { entries }
+    }
+}
+
+/// a row of the Farkas algorithm: the remaining columns of the matrix and the weights of the rows
+#[derive(PartialEq, Eq, Clone)]
+struct Row {
+    columns: Vec<i128>,
+    weights: Vec<i128>,
+}
+
+impl Row {
+    fn support(&self) -> impl Iterator<Item = usize> + '_ {
+        self.weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight != 0)
+            .map(|(i, _)| i)
+    }
+
+    /// true if the support of the row is a proper subset of the support of the other row
+    fn is_smaller(&self, other: &Row) -> bool {
+        self.support().all(|i| other.weights[i] != 0)
+            && self.support().count() < other.support().count()
+    }
+
+    /// divides all entries by their greatest common divisor
+    fn normalize(mut self) -> Row {
+        let divisor = self
+            .columns
+            .iter()
+            .chain(&self.weights)
+            .fold(0, |divisor, value| gcd(divisor, value.abs()));
+        if divisor > 1 {
+            for value in self.columns.iter_mut().chain(self.weights.iter_mut()) {
+                *value /= divisor;
+            }
+        }
+        self
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn overflow() -> PetriError {
+    PetriError::invalid(None, "the semiflows are too large to compute")
+}
+
+/// The minimal non-negative integer solutions y of y·matrix = 0, one weight per row of the
+/// matrix, sorted
+fn farkas(matrix: &[Vec<i64>], columns: usize) -> Result<Vec<Vec<usize>>> {
+    let mut rows: Vec<Row> = matrix
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut weights = vec![0; matrix.len()];
+            weights[i] = 1;
+            Row {
+                columns: row.iter().map(|value| i128::from(*value)).collect(),
+                weights,
+            }
+        })
+        .collect();
+    for column in 0..columns {
+        let (mut next, nonzero): (Vec<Row>, Vec<Row>) =
+            rows.into_iter().partition(|row| row.columns[column] == 0);
+        for positive in nonzero.iter().filter(|row| row.columns[column] > 0) {
+            for negative in nonzero.iter().filter(|row| row.columns[column] < 0) {
+                let (a, b) = (-negative.columns[column], positive.columns[column]);
+                let combine = |x: &i128, y: &i128| {
+                    x.checked_mul(a)
+                        .and_then(|x| y.checked_mul(b).and_then(|y| x.checked_add(y)))
+                        .ok_or_else(overflow)
+                };
+                let combined = Row {
+                    columns: (positive.columns.iter().zip(&negative.columns))
+                        .map(|(x, y)| combine(x, y))
+                        .collect::<Result<Vec<i128>>>()?,
+                    weights: (positive.weights.iter().zip(&negative.weights))
+                        .map(|(x, y)| combine(x, y))
+                        .collect::<Result<Vec<i128>>>()?,
+                };
+                next.push(combined.normalize());
+            }
+        }
+        // rows without minimal support are combinations of the others
+        let mut minimal: Vec<Row> = Vec::new();
+        for row in next.iter() {
+            if !minimal.contains(row) && !next.iter().any(|other| other.is_smaller(row)) {
+                minimal.push(row.clone());
+            }
+        }
+        rows = minimal;
+    }
+    let mut semiflows = rows
+        .into_iter()
+        .map(|row| {
+            row.weights
+                .into_iter()
+                .map(|weight| usize::try_from(weight).map_err(|_| overflow()))
+                .collect::<Result<Vec<usize>>>()
+        })
+        .collect::<Result<Vec<Vec<usize>>>>()?;
+    semiflows.sort_unstable_by(|a, b| b.cmp(a));
+    semiflows.dedup();
+    Ok(semiflows)
+}
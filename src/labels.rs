@@ -0,0 +1,93 @@
+//! Labels of pages and nets.
+//!
+//! Besides names the core model allows arbitrary labels on pages and nets, e.g. declarations
+//! or comments. They are not interpreted and kept as raw xml. A label is identified by the
+//! name of its element, its content is everything inside of it (usually a `<text>` element).
+
+use crate::pnml::*;
+use crate::pxml::{parse_raw_content, raw_content_to_string};
+use crate::*;
+
+/// elements of nets and pages that have a meaning in the core model
+const RESERVED_ELEMENTS: [&str; 10] = [
+    "name",
+    "graphics",
+    "toolspecific",
+    "page",
+    "place",
+    "transition",
+    "referencePlace",
+    "referenceTransition",
+    "arc",
+    "net",
+];
+
+fn unknown_label(id: &PNMLID, element: &str, content: &str) -> Result<LabelBase> {
+    if !is_xml_name(element) {
+        return Err(PetriError::invalid(
+            Some(id),
+            format!("{:?} is not a valid label element", element),
+        ));
+    }
+    if RESERVED_ELEMENTS.contains(&element) {
+        return Err(PetriError::invalid(
+            Some(id),
+            format!("{} is not a label", element),
+        ));
+    }
+    Ok(LabelBase::new(Label::Unknown {
+        element: element.into(),
+        content: parse_raw_content(content)?,
+    }))
+}
+
+/// the raw content of all unknown labels with the given element
+fn label_contents(labels: &[LabelBase], element: &str) -> Result<Vec<String>> {
+    labels
+        .iter()
+        .filter_map(|label| match &label.label {
+            Label::Unknown {
+                element: label_element,
+                content,
+            } if label_element == element => Some(raw_content_to_string(content)),
+            _ => None,
+        })
+        .collect()
+}
+
+impl PetriNet {
+    /// Attaches a label with the element name `element` to the net.
+    /// `content` is the raw xml inside the element, e.g. `<text>a comment</text>`.
+    pub fn add_label(&mut self, element: &str, content: &str) -> Result<&mut Self> {
+        let label = unknown_label(&self.id, element, content)?;
+        self.labels.push(label);
+        Ok(self)
+    }
+
+    /// the raw xml content of all labels of the net with the element name
+    pub fn get_labels(&self, element: &str) -> Result<Vec<String>> {
+        label_contents(&self.labels, element)
+    }
+}
+
+impl PageRef {
+    /// Attaches a label with the element name `element` to the page.
+    /// `content` is the raw xml inside the element, e.g. `<text>a comment</text>`.
+    pub fn add_label(
+        &mut self,
+        net: &mut PetriNet,
+        element: &str,
+        content: &str,
+    ) -> Result<&mut Self> {
+        let page = net.get_page_mut(self)?;
+        let label = unknown_label(&page.id, element, content)?;
+        page.labels.get_or_insert_with(Vec::new).push(label);
+        Ok(self)
+    }
+
+    /// the raw xml content of all labels of the page with the element name
+    pub fn get_labels(&self, net: &PetriNet, element: &str) -> Result<Vec<String>> {
+        let labels = net.get_page(self)?.labels.as_deref().unwrap_or(&[]);
+        label_contents(labels, element)
+    }
+}
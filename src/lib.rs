@@ -1,52 +1,68 @@
+mod arena;
+pub mod classes;
+pub mod compiled;
+pub mod coverability;
+pub mod deadlock;
+pub mod editing;
+pub mod grammar_check;
+pub mod graphics;
+pub mod invariants;
+pub mod labels;
+pub mod matrices;
 pub mod pnml;
 pub mod pt_net_package;
 pub mod pxml;
+pub mod reachability;
+pub mod reduction;
+mod relaxng;
+pub mod semantics;
+pub mod simulation;
+pub mod siphons;
 mod tests;
+pub mod tool_specific;
 mod trait_impls;
+pub mod traversal;
+pub mod validation;
 
 use crate::pnml::{ObjectBase, PNMLName, PNMLVersion, PNMLID};
 // pnml standard: http://cs.au.dk/fileadmin/site_files/cs/research_areas/centers_and_projects/cpn/paper06.pdf
 
 ///
 /// Implementation of parts of the pnml core model and the PT-Net extension to use it as input for petri net model checkers.
-///
-///
-///
-///### Unsupported:
-/// - graphics information for objects
-/// - page (or global) labels
-/// - tool specific information
 #[derive(Debug)]
 pub struct PNMLDocument {
     petri_nets: Vec<PetriNet>,
+    ids: pnml::IdRegistry,
 }
 
 pub type Result<T> = std::result::Result<T, PetriError>;
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
 pub struct PetriNetRef(usize);
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct PageRef {
-    net: PNMLID,
-    /// The path to the (sub) page as stack of indices
-    /// The first element of the stack is the index of petri net pages
-    /// The n+1th is the index of the sub page in the nth page
-    page_stack: Vec<usize>,
-}
+// Handles of pages, nodes and arcs are keys into the arena of their net.
+// They are cheap to copy, resolve in constant time and stay valid until
+// the object is removed, after which they are reported as not found.
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub struct PageRef(arena::Key);
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
 pub enum NodeRef {
-    PlaceRef { page: PageRef, obj_index: usize },
-    TransitionRef { page: PageRef, obj_index: usize },
+    PlaceRef(arena::Key),
+    TransitionRef(arena::Key),
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct ArcRef {
-    page: PageRef,
-    obj_index: usize,
-    source: NodeRef,
-    sink: NodeRef,
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub struct ArcRef(arena::Key);
+
+/// Handle of any object with an id, see [`PNMLDocument::find_by_id`]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum Handle {
+    Net(PetriNetRef),
+    Page(PageRef),
+    Node(NodeRef),
+    Arc(ArcRef),
 }
 
 #[derive(Debug)]
@@ -54,17 +70,119 @@ pub struct PetriNet {
     id: PNMLID,
     typ: PNMLVersion,
     name: PNMLName,
-    pages: Vec<ObjectBase>,
+    /// the top level pages
+    pages: Vec<arena::Key>,
+    /// all pages, nodes and arcs of the net
+    objects: arena::Arena<ObjectBase>,
+    tool_specific: Vec<tool_specific::ToolInfo>,
+    /// labels of the net which are not interpreted
+    labels: Vec<pnml::LabelBase>,
+    /// the ids of the whole document
+    ids: pnml::IdRegistry,
+    /// the handles of all pages, nodes and arcs in the net by their id
+    index: std::collections::HashMap<String, Handle>,
 }
 
+/// Errors of all operations on a [`PNMLDocument`].
+///
+/// Each variant carries the handle and/or PNML id of the offending object where it is known.
+/// Errors raised deep inside an operation are wrapped in [`PetriError::Context`] to tell
+/// which operation on which object failed.
+/// Use [`PetriError::kind`] to match on the kind of error independently of that wrapping.
+#[derive(Debug)]
 pub enum PetriError {
+    /// Edges are only allowed from places to transitions or vice versa
+    BipartitionViolation {
+        source: String,
+        target: String,
+    },
+    PlaceNotFound {
+        handle: Option<NodeRef>,
+        id: Option<String>,
+        /// the page the place was looked for on, if the operation is bound to one
+        page: Option<String>,
+    },
+    TransitionNotFound {
+        handle: Option<NodeRef>,
+        id: Option<String>,
+        /// the page the transition was looked for on, if the operation is bound to one
+        page: Option<String>,
+    },
+    ArcNotFound {
+        handle: Option<ArcRef>,
+        /// the page the arc was looked for on, if the operation is bound to one
+        page: Option<String>,
+    },
+    PageNotFound {
+        handle: Option<PageRef>,
+        id: Option<String>,
+    },
+    NetNotFound {
+        handle: PetriNetRef,
+    },
+    /// The id is already used by another object of the document
+    DuplicateId {
+        id: String,
+    },
+    /// Tried to use data in a place where it does not belong
+    InvalidData {
+        id: Option<String>,
+        reason: String,
+    },
+    /// The internal representation is inconsistent
+    CorruptedData {
+        id: Option<String>,
+        reason: String,
+    },
+    XmlWriterError(xml::writer::Error),
+    XmlReaderError(xml::reader::Error),
+    /// `source` occurred while performing `operation` on the object with the PNML `id`
+    Context {
+        operation: Operation,
+        id: Option<String>,
+        source: Box<PetriError>,
+    },
+}
+
+/// Stable, machine readable classification of a [`PetriError`]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum ErrorKind {
     BipartitionViolation,
     PlaceNotFound,
     TransitionNotFound,
+    ArcNotFound,
     PageNotFound,
     NetNotFound,
-    ObjectNotFound,
-    InvalidData(String),
-    CorruptedData(String),
-    XmlWriterError(xml::writer::Error),
+    DuplicateId,
+    InvalidData,
+    CorruptedData,
+    XmlWriterError,
+    XmlReaderError,
+}
+
+/// The operations that are reported in [`PetriError::Context`]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum Operation {
+    AddPage,
+    AddPlace,
+    AddTransition,
+    AddArc,
+    AddReferenceNode,
+    RemovePage,
+    RemoveNode,
+    RemoveArc,
+    MoveNode,
+    Flatten,
+    Fire,
+    Compile,
+    ResolveSource,
+    ResolveTarget,
+    ResolveReference,
+    SetName,
+    SetInitialMarking,
+    SetInscription,
+    ReadNet,
+    ReadPage,
+    ReadNode,
+    ReadArc,
 }
@@ -0,0 +1,164 @@
+//! Pre-, post- and incidence matrices of a place/transition net.
+//!
+//! The rows of all matrices are the places and the columns the transitions, in the
+//! order of the indices of the [`CompiledNet`]. The pre matrix holds the weight of the arc
+//! from a place to a transition, the post matrix the weight of the arc from a transition
+//! to a place and the incidence matrix is post - pre, the change of tokens on the place
+//! when the transition fires. Reference nodes are resolved, weights of parallel arcs are
+//! added up.
+
+use crate::compiled::CompiledNet;
+use crate::*;
+use std::convert::TryFrom;
+
+/// A matrix that only stores the entries that are not zero
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SparseMatrix<T> {
+    rows: usize,
+    columns: usize,
+    /// (row, column, value) ordered by row and column
+    entries: Vec<(usize, usize, T)>,
+}
+
+impl<T: Copy + Default + PartialEq> SparseMatrix<T> {
+    /// Builds the matrix from (row, column, value) entries in any order.
+    /// Zero entries are dropped, an entry given twice keeps the last value.
+    fn new(rows: usize, columns: usize, mut entries: Vec<(usize, usize, T)>) -> Self {
+        entries.reverse();
+        // the stable sort keeps the last entry first among equal positions
+        entries.sort_by_key(|(row, column, _)| (*row, *column));
+        entries.dedup_by_key(|(row, column, _)| (*row, *column));
+        entries.retain(|(_, _, value)| *value != T::default());
+        SparseMatrix {
+            rows,
+            columns,
+            entries,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// the value at the position, zero for positions that are not stored
+    pub fn get(&self, row: usize, column: usize) -> T {
+        self.entries
+            .binary_search_by_key(&(row, column), |(row, column, _)| (*row, *column))
+            .map_or_else(|_| T::default(), |i| self.entries[i].2)
+    }
+
+    /// all entries that are not zero as (row, column, value), ordered by row and column
+    pub fn entries(&self) -> &[(usize, usize, T)] {
+        &self.entries
+    }
+
+    /// the matrix as a list of rows
+    pub fn to_dense(&self) -> Vec<Vec<T>> {
+        let mut dense = vec![vec![T::default(); self.columns]; self.rows];
+        for (row, column, value) in &self.entries {
+            dense[*row][*column] = *value;
+        }
+        dense
+    }
+}
+
+/// The matrices of a net with the ids of the places and transitions,
+/// see the [module documentation](self)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct IncidenceMatrices {
+    place_ids: Vec<String>,
+    transition_ids: Vec<String>,
+    pre: SparseMatrix<usize>,
+    post: SparseMatrix<usize>,
+    incidence: SparseMatrix<i64>,
+}
+
+impl IncidenceMatrices {
+    /// the ids of the places, the labels of the rows
+    pub fn place_ids(&self) -> &[String] {
+        &self.place_ids
+    }
+
+    /// the ids of the transitions, the labels of the columns
+    pub fn transition_ids(&self) -> &[String] {
+        &self.transition_ids
+    }
+
+    /// the weights of the arcs from places to transitions
+    pub fn pre(&self) -> &SparseMatrix<usize> {
+        &self.pre
+    }
+
+    /// the weights of the arcs from transitions to places
+    pub fn post(&self) -> &SparseMatrix<usize> {
+        &self.post
+    }
+
+    /// post - pre, the change of the marking when a transition fires
+    pub fn incidence(&self) -> &SparseMatrix<i64> {
+        &self.incidence
+    }
+}
+
+impl PetriNet {
+    /// The matrices of the net, see [`IncidenceMatrices`]
+    pub fn incidence_matrices(&self) -> Result<IncidenceMatrices> {
+        self.compile()?.incidence_matrices()
+    }
+}
+
+impl CompiledNet {
+    /// The matrices of the net, see [`IncidenceMatrices`].
+    /// Fails if an arc weight does not fit into an entry of the incidence matrix.
+    pub fn incidence_matrices(&self) -> Result<IncidenceMatrices> {
+        let (places, transitions) = (self.place_count(), self.transition_count());
+        let mut pre = Vec::new();
+        let mut post = Vec::new();
+        for transition in 0..transitions {
+            pre.extend(
+                self.pre(transition)
+                    .map(|(place, w)| (place, transition, w)),
+            );
+            post.extend(
+                self.post(transition)
+                    .map(|(place, w)| (place, transition, w)),
+            );
+        }
+        let pre = SparseMatrix::new(places, transitions, pre);
+        let post = SparseMatrix::new(places, transitions, post);
+        let signed = |transition: usize, weight: usize| {
+            i64::try_from(weight).map_err(|_| {
+                PetriError::invalid(
+                    Some(&PNMLID::new(self.transition_id(transition))),
+                    format!(
+                        "the arc weight {} does not fit into the incidence matrix",
+                        weight
+                    ),
+                )
+            })
+        };
+        let mut incidence: Vec<(usize, usize, i64)> = post
+            .entries()
+            .iter()
+            .map(|(place, transition, w)| Ok((*place, *transition, signed(*transition, *w)?)))
+            .collect::<Result<_>>()?;
+        for (place, transition, w) in pre.entries() {
+            let produced = signed(*transition, post.get(*place, *transition))?;
+            // both weights are at most i64::MAX, the difference cannot overflow
+            incidence.push((*place, *transition, produced - signed(*transition, *w)?));
+        }
+        Ok(IncidenceMatrices {
+            place_ids: (0..places).map(|p| self.place_id(p).into()).collect(),
+            transition_ids: (0..transitions)
+                .map(|t| self.transition_id(t).into())
+                .collect(),
+            incidence: SparseMatrix::new(places, transitions, incidence),
+            pre,
+            post,
+        })
+    }
+}
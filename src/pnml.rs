@@ -1,9 +1,19 @@
+use crate::arena::{Arena, Key};
+use crate::graphics::{AnnotationGraphics, ArcGraphics, NodeGraphics};
+use crate::tool_specific::ToolInfo;
 use crate::*;
 use std;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 #[derive(Debug, Clone)]
-pub(crate) struct PNMLName(pub(crate) Option<String>);
+pub(crate) struct PNMLName {
+    pub(crate) text: Option<String>,
+    pub(crate) graphics: Option<AnnotationGraphics>,
+    pub(crate) tool_specific: Vec<ToolInfo>,
+}
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub(crate) struct PNMLID(pub(crate) String);
 
@@ -12,17 +22,24 @@ pub(crate) enum PNMLVersion {
     V2009,
 }
 
+/// The content of a page. The objects themselves are stored in the arena of the net,
+/// the page only keeps their order.
 #[derive(Debug)]
 pub(crate) struct Page {
-    pub(crate) objects: Vec<ObjectBase>,
-    pub(crate) sub_pages: Vec<ObjectBase>,
+    /// nodes and arcs
+    pub(crate) objects: Vec<Key>,
+    pub(crate) sub_pages: Vec<Key>,
 }
 
 #[derive(Debug)]
 pub(crate) struct ObjectBase {
     pub(crate) id: PNMLID,
+    /// the page the object is on, None for the (top level) pages of the net
+    pub(crate) page: Option<Key>,
     pub(crate) name: PNMLName,
-    pub(crate) labels: Option<Vec<Label>>,
+    pub(crate) graphics: Option<Graphics>,
+    pub(crate) labels: Option<Vec<LabelBase>>,
+    pub(crate) tool_specific: Vec<ToolInfo>,
     pub(crate) object: Object,
 }
 
@@ -37,8 +54,23 @@ pub(crate) enum Object {
 pub(crate) enum Node {
     Place,
     Transition,
-    PlaceRef(PNMLID, NodeRef),
-    TransitionRef(PNMLID, NodeRef),
+    /// links to the node with the id, which is looked up in the index of the net
+    PlaceRef(PNMLID),
+    TransitionRef(PNMLID),
+}
+
+#[derive(Debug)]
+pub(crate) enum Graphics {
+    /// for places, transitions, reference nodes and pages
+    Node(NodeGraphics),
+    Arc(ArcGraphics),
+}
+
+#[derive(Debug)]
+pub(crate) struct LabelBase {
+    pub(crate) label: Label,
+    pub(crate) graphics: Option<AnnotationGraphics>,
+    pub(crate) tool_specific: Vec<ToolInfo>,
 }
 
 #[derive(Debug)]
@@ -48,11 +80,44 @@ pub(crate) enum Label {
     /// Multiplicity of an arc
     /// cannot be zero by definition
     PTAnnotation(std::num::NonZeroUsize),
+    /// Any other label (declarations, comments, ...) of a page or net.
+    /// The content is kept as raw xml.
+    Unknown {
+        element: String,
+        content: Vec<xml::reader::XmlEvent>,
+    },
 }
 
-impl PNMLName {
-    pub fn new(name: &str) -> Self {
-        PNMLName(Some(name.into()))
+impl Page {
+    pub(crate) fn new() -> Self {
+        Page {
+            objects: Vec::new(),
+            sub_pages: Vec::new(),
+        }
+    }
+}
+
+impl ObjectBase {
+    pub(crate) fn new(id: PNMLID, name: PNMLName, object: Object) -> Self {
+        ObjectBase {
+            id,
+            page: None,
+            name,
+            graphics: None,
+            labels: None,
+            tool_specific: Vec::new(),
+            object,
+        }
+    }
+}
+
+impl LabelBase {
+    pub(crate) fn new(label: Label) -> Self {
+        LabelBase {
+            label,
+            graphics: None,
+            tool_specific: Vec::new(),
+        }
     }
 }
 
@@ -62,25 +127,95 @@ impl PNMLID {
     }
 }
 
+/// Checks that `name` can be used as xml element name or id (NCName)
+pub(crate) fn is_xml_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// The ids of all objects of a document.
+///
+/// The registry is shared by the document and all of its nets,
+/// so ids are unique in the whole document and not only in a net.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdRegistry(Arc<Mutex<HashSet<String>>>);
+
+impl IdRegistry {
+    fn ids(&self) -> MutexGuard<'_, HashSet<String>> {
+        // the set is consistent after every operation, a panic cannot leave it half done
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// reserves an id supplied by the user
+    pub(crate) fn reserve(&self, id: &str) -> Result<PNMLID> {
+        if !is_xml_name(id) {
+            return Err(PetriError::invalid(
+                None,
+                format!("{:?} is not a valid id", id),
+            ));
+        }
+        self.register(id)
+    }
+
+    /// reserves an id read from a document, which is only checked to be unique
+    pub(crate) fn register(&self, id: &str) -> Result<PNMLID> {
+        if !self.ids().insert(id.into()) {
+            return Err(PetriError::DuplicateId { id: id.into() });
+        }
+        Ok(PNMLID::new(id))
+    }
+
+    /// frees the id of a removed object
+    pub(crate) fn unregister(&self, id: &PNMLID) {
+        self.ids().remove(&id.0);
+    }
+
+    /// reserves the first unused id made of `prefix` and a number counting up from `start`
+    pub(crate) fn generate(&self, prefix: &str, start: usize) -> PNMLID {
+        let mut ids = self.ids();
+        let id = (start..)
+            .map(|n| format!("{}{}", prefix, n))
+            .find(|id| !ids.contains(id))
+            .expect("there is always an unused id");
+        ids.insert(id.clone());
+        PNMLID(id)
+    }
+}
+
 impl PNMLDocument {
     pub fn new() -> Self {
         PNMLDocument {
             petri_nets: Vec::new(),
+            ids: IdRegistry::default(),
         }
     }
 
     pub fn add_petri_net(&mut self, name: Option<&str>) -> PetriNetRef {
-        self.petri_nets.push(PetriNet {
-            id: PNMLID::new(&format!("n{}", self.petri_nets.len())),
-            typ: PNMLVersion::V2009,
-            name: name.into(),
-            pages: Vec::new(),
-        });
+        let id = self.ids.generate("n", self.petri_nets.len());
+        self.push_petri_net(id, name)
+    }
+
+    /// adds a net with an id supplied by the user, which must be unique in the document
+    pub fn add_petri_net_with_id(&mut self, id: &str, name: Option<&str>) -> Result<PetriNetRef> {
+        let id = self.ids.reserve(id)?;
+        Ok(self.push_petri_net(id, name))
+    }
+
+    fn push_petri_net(&mut self, id: PNMLID, name: Option<&str>) -> PetriNetRef {
+        self.petri_nets.push(PetriNet::new(id, self.ids.clone()));
+        let net = self.petri_nets.last_mut().expect("net was just added");
+        net.name = name.into();
         PetriNetRef(self.petri_nets.len() - 1)
     }
 
     pub fn petri_net_data(&mut self, net: PetriNetRef) -> Result<&mut PetriNet> {
-        self.petri_nets.get_mut(net.0).ok_or(PetriError::NetNotFound)
+        self.petri_nets
+            .get_mut(net.0)
+            .ok_or(PetriError::NetNotFound { handle: net })
     }
 
     pub fn petri_nets(&self) -> Vec<PetriNetRef> {
@@ -90,157 +225,397 @@ impl PNMLDocument {
             .map(|(i, _)| PetriNetRef(i))
             .collect()
     }
+
+    /// Searches the net, page, node or arc with the id in all nets of the document
+    pub fn find_by_id(&self, id: &str) -> Option<Handle> {
+        self.petri_nets.iter().enumerate().find_map(|(i, net)| {
+            if net.id.0 == id {
+                Some(Handle::Net(PetriNetRef(i)))
+            } else {
+                net.index.get(id).copied()
+            }
+        })
+    }
+}
+
+impl PetriNetRef {
+    /// the id the net is written with
+    pub fn id<'a>(&self, document: &'a PNMLDocument) -> Result<&'a str> {
+        document
+            .petri_nets
+            .get(self.0)
+            .map(|net| net.id.0.as_str())
+            .ok_or(PetriError::NetNotFound { handle: *self })
+    }
+}
+
+impl PageRef {
+    /// the id the page is written with
+    pub fn id<'a>(&self, net: &'a PetriNet) -> Result<&'a str> {
+        Ok(&net.get_page(self)?.id.0)
+    }
 }
 
 impl PetriNet {
+    /// an empty net whose ids are registered in `ids`
+    pub(crate) fn new(id: PNMLID, ids: IdRegistry) -> Self {
+        PetriNet {
+            id,
+            typ: PNMLVersion::V2009,
+            name: None.into(),
+            pages: Vec::new(),
+            objects: Arena::new(),
+            tool_specific: Vec::new(),
+            labels: Vec::new(),
+            ids,
+            index: HashMap::new(),
+        }
+    }
+
     pub fn pages(&self) -> Vec<PageRef> {
-        self.pages
-            .iter()
-            .enumerate()
-            // though we iterate other ObjectBases the indices are consistent
-            .map(|(i, _)| {
-                let mut page_stack = Vec::new();
-                page_stack.push(i);
-                PageRef {
-                    net: self.id.clone(),
-                    page_stack,
-                }
-            })
-            .collect()
+        self.pages.iter().map(|key| PageRef(*key)).collect()
     }
 
     pub fn add_page(&mut self, name: Option<&str>) -> PageRef {
-        self.pages.push(ObjectBase {
-            id: PNMLID::new(&format!("{}_p{}", self.id.0, self.pages.len())),
-            name: name.into(),
-            labels: None,
-            object: Object::Page(Page {
-                objects: Vec::new(),
-                sub_pages: Vec::new(),
-            }),
-        });
-        let mut page_stack = Vec::new();
-        page_stack.push(self.pages.len() - 1);
-        PageRef {
-            net: self.id.clone(),
-            page_stack,
-        }
+        let id = self
+            .ids
+            .generate(&format!("{}_p", self.id.0), self.pages.len());
+        self.insert_page(
+            ObjectBase::new(id, name.into(), Object::Page(Page::new())),
+            None,
+        )
+        .expect("pages of the net have no parent that could be missing")
+    }
+
+    /// adds a page with an id supplied by the user, which must be unique in the document
+    pub fn add_page_with_id(&mut self, id: &str, name: Option<&str>) -> Result<PageRef> {
+        let id = self
+            .ids
+            .reserve(id)
+            .map_err(|e| e.context(Operation::AddPage, None))?;
+        self.insert_page(
+            ObjectBase::new(id, name.into(), Object::Page(Page::new())),
+            None,
+        )
     }
 
     pub fn add_sub_page(&mut self, name: Option<&str>, parent: &PageRef) -> Result<PageRef> {
-        let page = self.get_page_mut(parent)?;
-        // remember the parent id to build a new one from it
-        let parent_id = page.id.0.clone();
-        // make the actual page out of the object
-        let page = PetriNet::obj_to_page_mut(page)?;
-        // finally add the page and return the reference
-        page.sub_pages.push(ObjectBase {
-            id: PNMLID::new(&format!("{}-{}", parent_id, page.sub_pages.len())),
-            name: name.into(),
-            labels: None,
-            object: Object::Page(Page {
-                objects: Vec::new(),
-                sub_pages: Vec::new(),
-            }),
-        });
-        let mut page_stack = parent.page_stack.clone();
-        page_stack.push(self.pages.len() - 1);
-        Ok(PageRef {
-            net: self.id.clone(),
-            page_stack,
-        })
+        let page = self.get_page(parent)?;
+        // build the new id from the parent id
+        let prefix = format!("{}-", page.id.0);
+        let id = self
+            .ids
+            .generate(&prefix, PetriNet::obj_to_page(page)?.sub_pages.len());
+        self.insert_page(
+            ObjectBase::new(id, name.into(), Object::Page(Page::new())),
+            Some(parent),
+        )
+    }
+
+    /// adds a sub page with an id supplied by the user, which must be unique in the document
+    pub fn add_sub_page_with_id(
+        &mut self,
+        id: &str,
+        name: Option<&str>,
+        parent: &PageRef,
+    ) -> Result<PageRef> {
+        self.get_page(parent)
+            .map_err(|e| e.context(Operation::AddPage, Some(&PNMLID::new(id))))?;
+        let id = self
+            .ids
+            .reserve(id)
+            .map_err(|e| e.context(Operation::AddPage, None))?;
+        self.insert_page(
+            ObjectBase::new(id, name.into(), Object::Page(Page::new())),
+            Some(parent),
+        )
+    }
+
+    /// Stores the page as last sub page of `parent` or as last page of the net.
+    /// The id of the page has to be reserved already.
+    pub(crate) fn insert_page(
+        &mut self,
+        mut page: ObjectBase,
+        parent: Option<&PageRef>,
+    ) -> Result<PageRef> {
+        if let Some(parent) = parent {
+            self.get_page(parent)?;
+        }
+        page.page = parent.map(|parent| parent.0);
+        let id = page.id.0.clone();
+        let key = self.objects.insert(page);
+        match parent {
+            Some(parent) => PetriNet::obj_to_page_mut(self.get_page_mut(parent)?)?
+                .sub_pages
+                .push(key),
+            None => self.pages.push(key),
+        }
+        self.index.insert(id, Handle::Page(PageRef(key)));
+        Ok(PageRef(key))
     }
 
     pub fn add_place(&mut self, page_ref: &PageRef) -> Result<NodeRef> {
-        let page = self.get_page_mut(page_ref)?;
-        let parent_id = page.id.0.clone();
-        let page = PetriNet::obj_to_page_mut(page)?;
-        page.objects.push(ObjectBase {
-            id: PNMLID::new(&format!("{}_o{}", parent_id, page.objects.len())),
-            name: None.into(),
-            labels: None,
-            object: Object::Node(Node::Place),
-        });
-        Ok(NodeRef::PlaceRef {
-            page: page_ref.clone(),
-            obj_index: page.objects.len() - 1,
-        })
+        let id = self.generate_object_id(page_ref, "_o")?;
+        self.push_node(page_ref, id, Node::Place)
+    }
+
+    /// adds a place with an id supplied by the user, which must be unique in the document
+    pub fn add_place_with_id(&mut self, page_ref: &PageRef, id: &str) -> Result<NodeRef> {
+        let id = self
+            .reserve_object_id(page_ref, id)
+            .map_err(|e| e.context(Operation::AddPlace, Some(&PNMLID::new(id))))?;
+        self.push_node(page_ref, id, Node::Place)
     }
 
     pub fn add_transition(&mut self, page_ref: &PageRef) -> Result<NodeRef> {
-        let page = self.get_page_mut(page_ref)?;
-        let parent_id = page.id.0.clone();
-        let page = PetriNet::obj_to_page_mut(page)?;
-        page.objects.push(ObjectBase {
-            id: PNMLID::new(&format!("{}_o{}", parent_id, page.objects.len())),
-            name: None.into(),
-            labels: None,
-            object: Object::Node(Node::Transition),
-        });
-        Ok(NodeRef::TransitionRef {
-            page: page_ref.clone(),
-            obj_index: page.objects.len() - 1,
-        })
+        let id = self.generate_object_id(page_ref, "_o")?;
+        self.push_node(page_ref, id, Node::Transition)
+    }
+
+    /// adds a transition with an id supplied by the user, which must be unique in the document
+    pub fn add_transition_with_id(&mut self, page_ref: &PageRef, id: &str) -> Result<NodeRef> {
+        let id = self
+            .reserve_object_id(page_ref, id)
+            .map_err(|e| e.context(Operation::AddTransition, Some(&PNMLID::new(id))))?;
+        self.push_node(page_ref, id, Node::Transition)
+    }
+
+    /// the first unused id made of the page id, `infix` and a number
+    fn generate_object_id(&self, page_ref: &PageRef, infix: &str) -> Result<PNMLID> {
+        let page = self.get_page(page_ref)?;
+        let prefix = format!("{}{}", page.id.0, infix);
+        Ok(self
+            .ids
+            .generate(&prefix, PetriNet::obj_to_page(page)?.objects.len()))
+    }
+
+    /// checks that the page exists before the id is taken
+    fn reserve_object_id(&self, page_ref: &PageRef, id: &str) -> Result<PNMLID> {
+        self.get_page(page_ref)?;
+        self.ids.reserve(id)
+    }
+
+    fn push_node(&mut self, page_ref: &PageRef, id: PNMLID, node: Node) -> Result<NodeRef> {
+        let key = self.insert_object(
+            page_ref,
+            ObjectBase::new(id, None.into(), Object::Node(node)),
+        )?;
+        match self.handle(key)? {
+            Handle::Node(node) => Ok(node),
+            _ => Err(PetriError::corrupted(
+                None,
+                "the node was not stored as node",
+            )),
+        }
     }
 
+    /// Stores the node or arc as last object of the page.
+    /// The id of the object has to be reserved already.
+    pub(crate) fn insert_object(&mut self, page_ref: &PageRef, mut obj: ObjectBase) -> Result<Key> {
+        self.get_page(page_ref)?;
+        obj.page = Some(page_ref.0);
+        let id = obj.id.0.clone();
+        let key = self.objects.insert(obj);
+        let handle = self.handle(key)?;
+        PetriNet::obj_to_page_mut(self.get_page_mut(page_ref)?)?
+            .objects
+            .push(key);
+        self.index.insert(id, handle);
+        Ok(key)
+    }
+}
+
+impl PetriNet {
     pub fn add_arc(
         &mut self,
         page_ref: &PageRef,
         source_ref: &NodeRef,
         sink_ref: &NodeRef,
     ) -> Result<ArcRef> {
+        let page = self.get_page(page_ref)?;
+        // the id is only reserved once the arc is known to be valid
+        let arc_id = PNMLID::new(&format!(
+            "{}_o{}",
+            page.id.0,
+            PetriNet::obj_to_page(page)?.objects.len()
+        ));
+        let page_id = page.id.clone();
+        let (source_id, sink_id) = self.arc_endpoints(source_ref, sink_ref).map_err(|e| {
+            e.on_page(&page_id)
+                .context(Operation::AddArc, Some(&arc_id))
+        })?;
+        let arc_id = self.generate_object_id(page_ref, "_o")?;
+        self.push_arc(page_ref, arc_id, source_id, sink_id)
+    }
+
+    /// adds an arc with an id supplied by the user, which must be unique in the document
+    pub fn add_arc_with_id(
+        &mut self,
+        page_ref: &PageRef,
+        id: &str,
+        source_ref: &NodeRef,
+        sink_ref: &NodeRef,
+    ) -> Result<ArcRef> {
+        let add_arc = |error: PetriError| error.context(Operation::AddArc, Some(&PNMLID::new(id)));
+        let page_id = self.get_page(page_ref).map_err(add_arc)?.id.clone();
+        let (source_id, sink_id) = self
+            .arc_endpoints(source_ref, sink_ref)
+            .map_err(|e| add_arc(e.on_page(&page_id)))?;
+        let arc_id = self.ids.reserve(id).map_err(add_arc)?;
+        self.push_arc(page_ref, arc_id, source_id, sink_id)
+    }
+
+    /// the ids of source and sink of a new arc, if they can be connected
+    fn arc_endpoints(&self, source_ref: &NodeRef, sink_ref: &NodeRef) -> Result<(PNMLID, PNMLID)> {
+        let source_id = self
+            .get_node_obj(source_ref)
+            .map_err(|e| e.context(Operation::ResolveSource, None))?
+            .id
+            .clone();
+        let sink_id = self
+            .get_node_obj(sink_ref)
+            .map_err(|e| e.context(Operation::ResolveTarget, None))?
+            .id
+            .clone();
         // make sure places and transitions are connected correctly
         match (&source_ref, &sink_ref) {
-            (NodeRef::PlaceRef { .. }, NodeRef::PlaceRef { .. }) => {
-                return Err(PetriError::BipartitionViolation)
-            }
-            (NodeRef::TransitionRef { .. }, NodeRef::TransitionRef { .. }) => {
-                return Err(PetriError::BipartitionViolation)
+            (NodeRef::PlaceRef { .. }, NodeRef::PlaceRef { .. })
+            | (NodeRef::TransitionRef { .. }, NodeRef::TransitionRef { .. }) => {
+                Err(PetriError::BipartitionViolation {
+                    source: source_id.0,
+                    target: sink_id.0,
+                })
             }
-            _ => {}
-        };
-        let source_id = self.get_node_obj_mut(source_ref)?.id.clone();
-        let sink_id = self.get_node_obj_mut(sink_ref)?.id.clone();
-        let page = self.get_page_mut(page_ref)?;
-        let parent_id = page.id.0.clone();
-        let page = PetriNet::obj_to_page_mut(page)?;
-        page.objects.push(ObjectBase {
-            id: PNMLID::new(&format!("{}_o{}", parent_id, page.objects.len())),
-            name: None.into(),
-            labels: None,
-            object: Object::Arc(source_id, sink_id),
-        });
-        Ok(ArcRef {
-            page: page_ref.clone(),
-            obj_index: page.objects.len() - 1,
-            source: source_ref.clone(),
-            sink: sink_ref.clone(),
-        })
+            _ => Ok((source_id, sink_id)),
+        }
+    }
+
+    fn push_arc(
+        &mut self,
+        page_ref: &PageRef,
+        arc_id: PNMLID,
+        source_id: PNMLID,
+        sink_id: PNMLID,
+    ) -> Result<ArcRef> {
+        let arc = ObjectBase::new(arc_id, None.into(), Object::Arc(source_id, sink_id));
+        Ok(ArcRef(self.insert_object(page_ref, arc)?))
     }
 
     /// Creates a PNML standard RefPlace or RefTrans respectively.
     ///
-    /// Unlike the NodeRef::* enum variants that are used to look up a position in the
+    /// Unlike the NodeRef::* enum variants that are used to look up an object in the
     /// internal data structure, a RefPlace/RefTrans is an object in the pnml standard,
     /// that is used as a link to a Place/Transition in another location (e.g. another
     /// page). The RefPlace/RefTrans represent the same Place/Transition they link to.
     /// Such a reference is helpful to mention the same Node on different pages (in the
     /// graphical representation)
-    pub fn add_reference_node(&mut self, reference: &NodeRef, page: &PageRef) -> Result<()> {
-        let ref_obj = self.get_node_obj(reference)?;
+    ///
+    /// The returned handle is a `NodeRef::PlaceRef` for reference places and a
+    /// `NodeRef::TransitionRef` for reference transitions, so it can be used as end of
+    /// arcs like the node itself. [`PetriNet::resolve_reference`] gives the linked node.
+    /// `reference` may be a reference node as well.
+    pub fn add_reference_node(&mut self, reference: &NodeRef, page: &PageRef) -> Result<NodeRef> {
+        let ref_obj = self
+            .get_node_obj(reference)
+            .map_err(|e| e.context(Operation::AddReferenceNode, None))?;
         let ref_id = ref_obj.id.clone();
+        let prefix = format!("{}_ref_o", ref_id.0);
         let reference_node = match reference {
-            NodeRef::PlaceRef { .. } => Node::PlaceRef(ref_id, reference.clone()),
-            NodeRef::TransitionRef { .. } => Node::TransitionRef(ref_id, reference.clone()),
+            NodeRef::PlaceRef { .. } => Node::PlaceRef(ref_id),
+            NodeRef::TransitionRef { .. } => Node::TransitionRef(ref_id),
         };
-        let ref_id = ref_obj.id.0.clone();
-        let page = PetriNet::obj_to_page_mut(self.get_page_mut(page)?)?;
-        page.objects.push(ObjectBase {
-            id: PNMLID::new(&format!("{}_ref_o{}", ref_id, page.objects.len())),
-            name: None.into(),
-            labels: None,
-            object: Object::Node(reference_node),
-        });
+        let start = PetriNet::obj_to_page(self.get_page(page)?)?.objects.len();
+        let id = self.ids.generate(&prefix, start);
+        self.push_node(page, id, reference_node)
+    }
+
+    /// All pages of the net including the nested sub pages.
+    /// Parents are listed before their children.
+    pub(crate) fn all_pages(&self) -> Vec<PageRef> {
+        let mut pages = Vec::new();
+        let mut stack: Vec<Key> = self.pages.iter().rev().copied().collect();
+        while let Some(key) = stack.pop() {
+            if let Some(Object::Page(page)) = self.objects.get(key).map(|obj| &obj.object) {
+                stack.extend(page.sub_pages.iter().rev());
+                pages.push(PageRef(key));
+            }
+        }
+        pages
+    }
+
+    /// All nodes and arcs of all pages with their key.
+    /// Pages are visited in the order of [`PetriNet::all_pages`].
+    pub(crate) fn objects(&self) -> Vec<(Key, &ObjectBase)> {
+        let mut objects = Vec::new();
+        for page_ref in self.all_pages() {
+            if let Ok(page) = self.get_page(&page_ref).and_then(PetriNet::obj_to_page) {
+                for key in &page.objects {
+                    if let Some(obj) = self.objects.get(*key) {
+                        objects.push((*key, obj));
+                    }
+                }
+            }
+        }
+        objects
+    }
+
+    /// The handle of the object with the key
+    pub(crate) fn handle(&self, key: Key) -> Result<Handle> {
+        let obj = self
+            .objects
+            .get(key)
+            .ok_or_else(|| PetriError::corrupted(None, "no object is stored for the key"))?;
+        Ok(match &obj.object {
+            Object::Node(node) => Handle::Node(NodeRef::new(key, node)),
+            Object::Arc(..) => Handle::Arc(ArcRef(key)),
+            Object::Page(_) => Handle::Page(PageRef(key)),
+        })
+    }
+
+    /// the node with the id, if there is one
+    pub(crate) fn node_by_id(&self, id: &PNMLID) -> Option<NodeRef> {
+        match self.index.get(&id.0) {
+            Some(Handle::Node(node)) => Some(*node),
+            _ => None,
+        }
+    }
+
+    /// Checks that every RefPlace/RefTrans links to an existing node of the same type.
+    ///
+    /// While reading a document references can appear before the node they link to,
+    /// so they can only be checked after the whole net is known.
+    pub(crate) fn check_reference_nodes(&self) -> Result<()> {
+        for (key, obj) in self.objects() {
+            let target = match &obj.object {
+                Object::Node(Node::PlaceRef(target))
+                | Object::Node(Node::TransitionRef(target)) => target,
+                _ => continue,
+            };
+            let reference = self.handle(key)?;
+            match (reference, self.node_by_id(target)) {
+                (Handle::Node(NodeRef::PlaceRef { .. }), Some(NodeRef::PlaceRef { .. }))
+                | (
+                    Handle::Node(NodeRef::TransitionRef { .. }),
+                    Some(NodeRef::TransitionRef { .. }),
+                ) => {}
+                (_, Some(_)) => {
+                    return Err(PetriError::invalid(
+                        Some(&obj.id),
+                        format!("links to {} which is of a different node type", target.0),
+                    ))
+                }
+                (reference, None) => {
+                    let (handle, id, page) = (None, Some(target.0.clone()), None);
+                    let error = match reference {
+                        Handle::Node(NodeRef::TransitionRef { .. }) => {
+                            PetriError::TransitionNotFound { handle, id, page }
+                        }
+                        _ => PetriError::PlaceNotFound { handle, id, page },
+                    };
+                    return Err(error.context(Operation::ResolveReference, Some(&obj.id)));
+                }
+            }
+        }
         Ok(())
     }
 
@@ -248,7 +623,7 @@ impl PetriNet {
         const WRONG_OBJECT_MSG: &str = "Object in the sub pages array which is no Page";
         match &mut o.object {
             Object::Page(page) => Ok(page),
-            _ => return Err(PetriError::CorruptedData(WRONG_OBJECT_MSG.into())),
+            _ => Err(PetriError::corrupted(Some(&o.id), WRONG_OBJECT_MSG)),
         }
     }
 
@@ -256,147 +631,153 @@ impl PetriNet {
         const WRONG_OBJECT_MSG: &str = "Object in the sub pages array which is no Page";
         match &o.object {
             Object::Page(page) => Ok(page),
-            _ => return Err(PetriError::CorruptedData(WRONG_OBJECT_MSG.into())),
+            _ => Err(PetriError::corrupted(Some(&o.id), WRONG_OBJECT_MSG)),
         }
     }
 
     /// searches the referenced page in the net
     /// returns an ObjectBase because there is additional information stored (like the PNMLID)
     pub(crate) fn get_page_mut(&mut self, page: &PageRef) -> Result<&mut ObjectBase> {
-        const EMPTY_STACK_MSG: &str = "Invalid PageRef: Stack was empty";
-        /// get the sub page with index i from page_base
-        fn get_sub_page(page_base: &mut ObjectBase, i: usize) -> Result<&mut ObjectBase> {
-            PetriNet::obj_to_page_mut(page_base)?
-                .sub_pages
-                .get_mut(i)
-                .ok_or(PetriError::PageNotFound)
+        match self.objects.get_mut(page.0) {
+            Some(obj) if matches!(obj.object, Object::Page(_)) => Ok(obj),
+            _ => Err(page.not_found()),
         }
-        // clone the index array to mess around safely
-        let mut stack = page.page_stack.clone();
-        // get the initial page that is no sub page (stored directly in a PetriNet)
-        // thank you auto formatter :D
-        let mut page = self
-            .pages
-            .get_mut(
-                stack
-                    .pop()
-                    .ok_or(PetriError::CorruptedData(EMPTY_STACK_MSG.into()))?,
-            )
-            .ok_or(PetriError::PageNotFound)?;
-        // traverse the sub and subsub pages
-        for index in stack {
-            page = get_sub_page(page, index)?;
-        }
-        Ok(page)
     }
 
     pub(crate) fn get_page(&self, page: &PageRef) -> Result<&ObjectBase> {
-        const EMPTY_STACK_MSG: &str = "Invalid PageRef: Stack was empty";
-        /// get the sub page with index i from page_base
-        fn get_sub_page(page_base: &ObjectBase, i: usize) -> Result<&ObjectBase> {
-            PetriNet::obj_to_page(page_base)?
-                .sub_pages
-                .get(i)
-                .ok_or(PetriError::PageNotFound)
-        }
-        // clone the index array to mess around safely
-        let mut stack = page.page_stack.clone();
-        // get the initial page that is no sub page (stored directly in a PetriNet)
-        // thank you auto formatter :D
-        let mut page = self
-            .pages
-            .get(
-                stack
-                    .pop()
-                    .ok_or(PetriError::CorruptedData(EMPTY_STACK_MSG.into()))?,
-            )
-            .ok_or(PetriError::PageNotFound)?;
-        // traverse the sub and subsub pages
-        for index in stack {
-            page = get_sub_page(page, index)?;
+        match self.objects.get(page.0) {
+            Some(obj) if matches!(obj.object, Object::Page(_)) => Ok(obj),
+            _ => Err(page.not_found()),
         }
-        Ok(page)
     }
 
     pub(crate) fn get_node_obj_mut(&mut self, node: &NodeRef) -> Result<&mut ObjectBase> {
-        let (page_ref, obj_index) = match node {
-            NodeRef::PlaceRef { page, obj_index } => (page, obj_index),
-            NodeRef::TransitionRef { page, obj_index } => (page, obj_index),
-        };
-        let page = PetriNet::obj_to_page_mut(self.get_page_mut(&page_ref)?)?;
-        Ok(page
-            .objects
-            .get_mut(*obj_index)
-            .ok_or(PetriError::ObjectNotFound)?)
+        self.get_node_obj(node)?;
+        self.objects
+            .get_mut(node.key())
+            .ok_or_else(|| node.not_found())
     }
 
     pub(crate) fn get_node_obj(&self, node: &NodeRef) -> Result<&ObjectBase> {
-        let (page_ref, obj_index) = match node {
-            NodeRef::PlaceRef { page, obj_index } => (page, obj_index),
-            NodeRef::TransitionRef { page, obj_index } => (page, obj_index),
-        };
-        let page = PetriNet::obj_to_page(self.get_page(&page_ref)?)?;
-        Ok(page
-            .objects
-            .get(*obj_index)
-            .ok_or(PetriError::ObjectNotFound)?)
+        match self.objects.get(node.key()) {
+            Some(obj) if node.matches(obj) => Ok(obj),
+            obj => Err(self.not_found_on_page(node.not_found(), obj)),
+        }
     }
 
     pub(crate) fn get_arc_obj_mut(&mut self, arc: &ArcRef) -> Result<&mut ObjectBase> {
-        let page = PetriNet::obj_to_page_mut(self.get_page_mut(&arc.page)?)?;
-        let arc = page
-            .objects
-            .get_mut(arc.obj_index)
-            .ok_or(PetriError::ObjectNotFound)?;
-        assert!(match arc.object {
-            Object::Arc { .. } => true,
-            _ => false,
-        });
-        Ok(arc)
+        self.get_arc_obj(arc)?;
+        self.objects.get_mut(arc.0).ok_or_else(|| arc.not_found())
     }
 
     pub(crate) fn get_arc_obj(&self, arc: &ArcRef) -> Result<&ObjectBase> {
-        let page = PetriNet::obj_to_page(self.get_page(&arc.page)?)?;
-        let arc = page
-            .objects
-            .get(arc.obj_index)
-            .ok_or(PetriError::ObjectNotFound)?;
-        assert!(match arc.object {
-            Object::Arc { .. } => true,
-            _ => false,
-        });
-        Ok(arc)
+        match self.objects.get(arc.0) {
+            Some(obj) if matches!(obj.object, Object::Arc { .. }) => Ok(obj),
+            obj => Err(self.not_found_on_page(arc.not_found(), obj)),
+        }
+    }
+
+    /// adds the page of the object the handle points to, if it points to an object of
+    /// another kind
+    fn not_found_on_page(&self, error: PetriError, obj: Option<&ObjectBase>) -> PetriError {
+        match obj.and_then(|obj| self.objects.get(obj.page?)) {
+            Some(page) => error.on_page(&page.id),
+            None => error,
+        }
+    }
+}
+
+impl PageRef {
+    pub(crate) fn not_found(&self) -> PetriError {
+        PetriError::PageNotFound {
+            handle: Some(*self),
+            id: None,
+        }
     }
 }
 
 impl NodeRef {
-    pub fn name(&mut self, net: &mut PetriNet, name: &str) -> Result<(&mut Self)> {
-        let mut obj = net.get_node_obj_mut(self)?;
-        obj.name = PNMLName::new(name);
+    /// the handle of the node stored with the key
+    pub(crate) fn new(key: Key, node: &Node) -> Self {
+        match node {
+            Node::Place | Node::PlaceRef(..) => NodeRef::PlaceRef(key),
+            Node::Transition | Node::TransitionRef(..) => NodeRef::TransitionRef(key),
+        }
+    }
+
+    pub(crate) fn key(&self) -> Key {
+        match self {
+            NodeRef::PlaceRef(key) | NodeRef::TransitionRef(key) => *key,
+        }
+    }
+
+    /// checks if the object is a node of the kind of this handle
+    pub(crate) fn matches(&self, obj: &ObjectBase) -> bool {
+        matches!(
+            (self, &obj.object),
+            (NodeRef::PlaceRef { .. }, Object::Node(Node::Place))
+                | (NodeRef::PlaceRef { .. }, Object::Node(Node::PlaceRef(..)))
+                | (
+                    NodeRef::TransitionRef { .. },
+                    Object::Node(Node::Transition)
+                )
+                | (
+                    NodeRef::TransitionRef { .. },
+                    Object::Node(Node::TransitionRef(..))
+                )
+        )
+    }
+
+    pub(crate) fn not_found(&self) -> PetriError {
+        let (handle, id, page) = (Some(*self), None, None);
+        match self {
+            NodeRef::PlaceRef { .. } => PetriError::PlaceNotFound { handle, id, page },
+            NodeRef::TransitionRef { .. } => PetriError::TransitionNotFound { handle, id, page },
+        }
+    }
+
+    pub fn name(&mut self, net: &mut PetriNet, name: &str) -> Result<&mut Self> {
+        let obj = net
+            .get_node_obj_mut(self)
+            .map_err(|e| e.context(Operation::SetName, None))?;
+        obj.name.text = Some(name.into());
         Ok(self)
     }
 
     pub fn get_name<'a>(&'a self, net: &'a PetriNet) -> Result<Option<&'a str>> {
         let obj = net.get_node_obj(self)?;
-        match &obj.name {
-            PNMLName(Some(name)) => Ok(Some(&name)),
-            PNMLName(None) => Ok(None),
-        }
+        Ok(obj.name.text.as_deref())
+    }
+
+    /// the id the node is written with
+    pub fn id<'a>(&self, net: &'a PetriNet) -> Result<&'a str> {
+        Ok(&net.get_node_obj(self)?.id.0)
     }
 }
 
 impl ArcRef {
+    pub(crate) fn not_found(&self) -> PetriError {
+        PetriError::ArcNotFound {
+            handle: Some(*self),
+            page: None,
+        }
+    }
+
     pub fn name(&mut self, net: &mut PetriNet, name: &str) -> Result<&mut Self> {
-        let mut obj = net.get_arc_obj_mut(self)?;
-        obj.name = PNMLName::new(name);
+        let obj = net
+            .get_arc_obj_mut(self)
+            .map_err(|e| e.context(Operation::SetName, None))?;
+        obj.name.text = Some(name.into());
         Ok(self)
     }
 
     pub fn get_name<'a>(&'a self, net: &'a PetriNet) -> Result<Option<&'a str>> {
         let obj = net.get_arc_obj(self)?;
-        match &obj.name {
-            PNMLName(Some(name)) => Ok(Some(&name)),
-            PNMLName(None) => Ok(None),
-        }
+        Ok(obj.name.text.as_deref())
+    }
+
+    /// the id the arc is written with
+    pub fn id<'a>(&self, net: &'a PetriNet) -> Result<&'a str> {
+        Ok(&net.get_arc_obj(self)?.id.0)
     }
 }
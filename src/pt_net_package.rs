@@ -1,29 +1,55 @@
 use crate::pnml::*;
 use crate::*;
 
+/// replaces the value of the label, keeping its graphics and tool specific blocks
+fn set_label(labels: &mut Option<Vec<LabelBase>>, is_label: fn(&Label) -> bool, label: Label) {
+    let labels = labels.get_or_insert_with(Vec::new);
+    match labels.iter_mut().find(|x| is_label(&x.label)) {
+        Some(existing) => existing.label = label,
+        None => labels.push(LabelBase::new(label)),
+    }
+}
+
 impl NodeRef {
+    /// Sets the initial marking of the place.
+    /// The marking of a reference place is set on the place it links to.
     pub fn initial_marking(&mut self, net: &mut PetriNet, label: usize) -> Result<&mut Self> {
         const ERROR: &str = "transitions cannot have a marking";
         match self {
             NodeRef::PlaceRef { .. } => {
-                let mut obj = net.get_node_obj_mut(self)?;
-                match obj.labels {
-                    None => {
-                        obj.labels = Some(Vec::new());
-                    }
-                    _ => {}
-                };
-                let labels = obj.labels.as_mut().expect("this should not happen"); // we've just initialized you!
-                labels.retain(|x| match x {
-                    Label::PTMarking(_) => false,
-                    _ => true,
-                });
-                labels.push(Label::PTMarking(label));
+                let set_marking = |e: PetriError| e.context(Operation::SetInitialMarking, None);
+                let place = net.resolve_reference(self).map_err(set_marking)?;
+                let obj = net.get_node_obj_mut(&place).map_err(set_marking)?;
+                set_label(
+                    &mut obj.labels,
+                    |x| matches!(x, Label::PTMarking(_)),
+                    Label::PTMarking(label),
+                );
                 Ok(self)
             }
-            NodeRef::TransitionRef { .. } => Err(PetriError::InvalidData(String::from(ERROR))),
+            NodeRef::TransitionRef { .. } => {
+                let id = net.get_node_obj(self)?.id.clone();
+                Err(PetriError::invalid(Some(&id), ERROR))
+            }
         }
     }
+
+    /// the initial marking of the place (or the place a reference place links to),
+    /// 0 if none is set
+    pub fn get_initial_marking(&self, net: &PetriNet) -> Result<usize> {
+        if let NodeRef::TransitionRef { .. } = self {
+            return Err(PetriError::invalid(
+                Some(&net.get_node_obj(self)?.id),
+                "transitions have no marking",
+            ));
+        }
+        let obj = net.get_node_obj(&net.resolve_reference(self)?)?;
+        let marking = obj.labels.iter().flatten().find_map(|x| match x.label {
+            Label::PTMarking(marking) => Some(marking),
+            _ => None,
+        });
+        Ok(marking.unwrap_or(0))
+    }
 }
 
 impl ArcRef {
@@ -33,19 +59,24 @@ impl ArcRef {
         net: &mut PetriNet,
         label: std::num::NonZeroUsize,
     ) -> Result<&mut Self> {
-        let mut obj = net.get_arc_obj_mut(self)?;
-        match obj.labels {
-            None => {
-                obj.labels = Some(Vec::new());
-            }
-            _ => {}
-        };
-        let labels = obj.labels.as_mut().expect("this should not happen"); // we've just initialized you!
-        labels.retain(|x| match x {
-            Label::PTAnnotation(_) => false,
-            _ => true,
-        });
-        labels.push(Label::PTAnnotation(label));
+        let obj = net
+            .get_arc_obj_mut(self)
+            .map_err(|e| e.context(Operation::SetInscription, None))?;
+        set_label(
+            &mut obj.labels,
+            |x| matches!(x, Label::PTAnnotation(_)),
+            Label::PTAnnotation(label),
+        );
         Ok(self)
     }
+
+    /// the weight/multiplicity of the arc, 1 if none is set
+    pub fn get_inscription(&self, net: &PetriNet) -> Result<std::num::NonZeroUsize> {
+        let obj = net.get_arc_obj(self)?;
+        let inscription = obj.labels.iter().flatten().find_map(|x| match x.label {
+            Label::PTAnnotation(weight) => Some(weight),
+            _ => None,
+        });
+        Ok(inscription.unwrap_or(std::num::NonZeroUsize::new(1).unwrap()))
+    }
 }
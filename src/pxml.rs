@@ -1,8 +1,15 @@
 use crate::{
-    pnml::{Label, Node, Object, ObjectBase, Page},
-    PNMLDocument, PNMLName, PetriNet, Result,
+    arena::Key,
+    graphics::{AnnotationGraphics, ArcGraphics, Coordinate, Fill, Font, Line, NodeGraphics},
+    pnml::{Graphics, IdRegistry, Label, LabelBase, Node, Object, ObjectBase, Page, PNMLID},
+    tool_specific::ToolInfo,
+    Operation, PNMLDocument, PNMLName, PageRef, PetriError, PetriNet, Result,
 };
+use std::io::Read;
 use xml;
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+use xml::reader::{EventReader, XmlEvent as ReaderEvent};
 use xml::writer::{EmitterConfig, XmlEvent};
 
 impl PNMLDocument {
@@ -45,11 +52,17 @@ where
     T: std::io::Write,
 {
     fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
-        if let PNMLName(Some(name)) = &self {
+        if let Some(name) = &self.text {
             writer.write(XmlEvent::start_element("name"))?;
             writer.write(XmlEvent::start_element("text"))?;
             writer.write(XmlEvent::Characters(name))?;
             writer.write(XmlEvent::end_element())?;
+            if let Some(graphics) = &self.graphics {
+                graphics.write_xml(writer)?;
+            }
+            for info in &self.tool_specific {
+                info.write_xml(writer)?;
+            }
             writer.write(XmlEvent::end_element())?;
         };
         Ok(())
@@ -67,29 +80,45 @@ where
                 .attr("type", &self.typ.to_string()),
         )?;
         self.name.write_xml(writer)?;
+        for info in &self.tool_specific {
+            info.write_xml(writer)?;
+        }
+        for label in &self.labels {
+            label.write_xml(writer)?;
+        }
         for page in &self.pages {
-            page.write_xml(writer)?;
+            self.write_object(*page, writer)?;
         }
         writer.write(XmlEvent::end_element())?;
         Ok(())
     }
 }
 
-impl<T> XmlAble<T> for Page
-where
-    T: std::io::Write,
-{
-    fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
-        for object in &self.objects {
-            object.write_xml(writer)?;
-        }
-        for page in &self.sub_pages {
-            page.write_xml(writer)?;
+impl PetriNet {
+    /// writes the object with the key, pages with all of their content
+    fn write_object<T: std::io::Write>(
+        &self,
+        key: Key,
+        writer: &mut xml::writer::EventWriter<T>,
+    ) -> Result<()> {
+        let obj = self
+            .objects
+            .get(key)
+            .ok_or_else(|| PetriError::corrupted(None, "a page contains a removed object"))?;
+        obj.write_xml(writer)?;
+        // write page content if its a page
+        if let Object::Page(page) = &obj.object {
+            for key in page.objects.iter().chain(&page.sub_pages) {
+                self.write_object(*key, writer)?;
+            }
         }
+        writer.write(XmlEvent::end_element())?;
         Ok(())
     }
 }
 
+/// Writes the start element and the contained tags.
+/// The end element is written by [`PetriNet::write_object`] after the content of pages.
 impl<T> XmlAble<T> for ObjectBase
 where
     T: std::io::Write,
@@ -109,45 +138,221 @@ where
         {
             // write the contained tags
             self.name.write_xml(writer)?;
+            if let Some(graphics) = &self.graphics {
+                graphics.write_xml(writer)?;
+            }
             if let Some(labels) = &self.labels {
                 for label in labels {
                     label.write_xml(writer)?;
                 }
             }
-            // write page content if its a page
-            match &self.object {
-                Object::Page(page) => page.write_xml(writer)?,
-                _ => {}
+            for info in &self.tool_specific {
+                info.write_xml(writer)?;
             }
-        } // end of internal tags -> write the end element
-        writer.write(XmlEvent::end_element())?;
+        }
         Ok(())
     }
 }
 
-impl<T> XmlAble<T> for Label
+impl<T> XmlAble<T> for LabelBase
 where
     T: std::io::Write,
 {
     fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
-        match self {
+        match &self.label {
             Label::PTAnnotation(weight) => {
-                writer.write(XmlEvent::start_element("inscription"))?;
-                writer.write(XmlEvent::start_element("text"))?;
-                writer.write(XmlEvent::Characters(&weight.to_string()))?;
+                write_text_label(writer, "inscription", &weight.to_string())?
             }
             Label::PTMarking(mark_count) => {
-                writer.write(XmlEvent::start_element("initialMarking"))?;
-                writer.write(XmlEvent::start_element("text"))?;
-                writer.write(XmlEvent::Characters(&mark_count.to_string()))?;
+                write_text_label(writer, "initialMarking", &mark_count.to_string())?
+            }
+            Label::Unknown { element, content } => {
+                writer.write(XmlEvent::start_element(element.as_str()))?;
+                write_raw_content(writer, content)?;
             }
         };
+        if let Some(graphics) = &self.graphics {
+            graphics.write_xml(writer)?;
+        }
+        for info in &self.tool_specific {
+            info.write_xml(writer)?;
+        }
         writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl<T> XmlAble<T> for ToolInfo
+where
+    T: std::io::Write,
+{
+    fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
+        writer.write(
+            XmlEvent::start_element("toolspecific")
+                .attr("tool", &self.tool)
+                .attr("version", &self.version),
+        )?;
+        write_raw_content(writer, &self.content)?;
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl<T> XmlAble<T> for Graphics
+where
+    T: std::io::Write,
+{
+    fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
+        writer.write(XmlEvent::start_element("graphics"))?;
+        match self {
+            Graphics::Node(graphics) => {
+                write_coordinate(writer, "position", &graphics.position)?;
+                if let Some(dimension) = &graphics.dimension {
+                    write_coordinate(writer, "dimension", dimension)?;
+                }
+                if let Some(fill) = &graphics.fill {
+                    fill.write_xml(writer)?;
+                }
+                if let Some(line) = &graphics.line {
+                    line.write_xml(writer)?;
+                }
+            }
+            Graphics::Arc(graphics) => {
+                for position in &graphics.positions {
+                    write_coordinate(writer, "position", position)?;
+                }
+                if let Some(line) = &graphics.line {
+                    line.write_xml(writer)?;
+                }
+            }
+        }
         writer.write(XmlEvent::end_element())?;
         Ok(())
     }
 }
 
+impl<T> XmlAble<T> for AnnotationGraphics
+where
+    T: std::io::Write,
+{
+    fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
+        writer.write(XmlEvent::start_element("graphics"))?;
+        write_coordinate(writer, "offset", &self.offset)?;
+        if let Some(fill) = &self.fill {
+            fill.write_xml(writer)?;
+        }
+        if let Some(line) = &self.line {
+            line.write_xml(writer)?;
+        }
+        if let Some(font) = &self.font {
+            font.write_xml(writer)?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl<T> XmlAble<T> for Fill
+where
+    T: std::io::Write,
+{
+    fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
+        let gradient_rotation = self.gradient_rotation.map(|r| r.to_string());
+        write_empty_element(
+            writer,
+            "fill",
+            &[
+                ("color", self.color.as_deref()),
+                ("gradient-color", self.gradient_color.as_deref()),
+                ("gradient-rotation", gradient_rotation.as_deref()),
+                ("image", self.image.as_deref()),
+            ],
+        )
+    }
+}
+
+impl<T> XmlAble<T> for Line
+where
+    T: std::io::Write,
+{
+    fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
+        let shape = self.shape.map(|shape| shape.to_string());
+        let width = self.width.map(|width| width.to_string());
+        let style = self.style.map(|style| style.to_string());
+        write_empty_element(
+            writer,
+            "line",
+            &[
+                ("shape", shape.as_deref()),
+                ("color", self.color.as_deref()),
+                ("width", width.as_deref()),
+                ("style", style.as_deref()),
+            ],
+        )
+    }
+}
+
+impl<T> XmlAble<T> for Font
+where
+    T: std::io::Write,
+{
+    fn write_xml(&self, writer: &mut xml::writer::EventWriter<T>) -> Result<()> {
+        let rotation = self.rotation.map(|rotation| rotation.to_string());
+        write_empty_element(
+            writer,
+            "font",
+            &[
+                ("family", self.family.as_deref()),
+                ("style", self.style.as_deref()),
+                ("weight", self.weight.as_deref()),
+                ("size", self.size.as_deref()),
+                ("decoration", self.decoration.as_deref()),
+                ("align", self.align.as_deref()),
+                ("rotation", rotation.as_deref()),
+            ],
+        )
+    }
+}
+
+/// writes the start element of a label and its `<text>` element
+fn write_text_label<T: std::io::Write>(
+    writer: &mut xml::writer::EventWriter<T>,
+    element: &str,
+    text: &str,
+) -> Result<()> {
+    writer.write(XmlEvent::start_element(element))?;
+    writer.write(XmlEvent::start_element("text"))?;
+    writer.write(XmlEvent::Characters(text))?;
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+fn write_coordinate<T: std::io::Write>(
+    writer: &mut xml::writer::EventWriter<T>,
+    element: &str,
+    coordinate: &Coordinate,
+) -> Result<()> {
+    let (x, y) = (coordinate.x.to_string(), coordinate.y.to_string());
+    write_empty_element(writer, element, &[("x", Some(&x)), ("y", Some(&y))])
+}
+
+/// writes an element without content that has only the attributes which are set
+fn write_empty_element<T: std::io::Write>(
+    writer: &mut xml::writer::EventWriter<T>,
+    element: &str,
+    attributes: &[(&str, Option<&str>)],
+) -> Result<()> {
+    let mut start_element = XmlEvent::start_element(element);
+    for (name, value) in attributes {
+        if let Some(value) = value {
+            start_element = start_element.attr(*name, value);
+        }
+    }
+    writer.write(start_element)?;
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
 trait XmlElementAble {
     fn start_element(&self) -> xml::writer::events::StartElementBuilder<'_>;
 }
@@ -163,10 +368,525 @@ impl XmlElementAble for Node {
         match self {
             Node::Place => XmlEvent::start_element("place"),
             Node::Transition => XmlEvent::start_element("transition"),
-            Node::PlaceRef(id, _) => XmlEvent::start_element("referencePlace").attr("ref", &id.0),
-            Node::TransitionRef(id, _) => {
+            Node::PlaceRef(id) => XmlEvent::start_element("referencePlace").attr("ref", &id.0),
+            Node::TransitionRef(id) => {
                 XmlEvent::start_element("referenceTransition").attr("ref", &id.0)
             }
         }
     }
 }
+
+impl PNMLDocument {
+    /// Parses a pnml document from a string
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        PNMLDocument::from_reader(xml.as_bytes())
+    }
+
+    /// Parses a pnml document from a byte source like an opened `.pnml` file.
+    ///
+    /// Elements that are not part of the supported model are skipped.
+    /// Fails on duplicate ids and on reference nodes that link to a missing node or to a
+    /// node of the other kind.
+    pub fn from_reader<R: Read>(source: R) -> Result<Self> {
+        PNMLDocument::read(source, false)
+    }
+
+    /// Parses a pnml document from a string, see [`PNMLDocument::from_reader_lenient`]
+    pub fn from_xml_lenient(xml: &str) -> Result<Self> {
+        PNMLDocument::from_reader_lenient(xml.as_bytes())
+    }
+
+    /// Parses a pnml document like [`PNMLDocument::from_reader`], but keeps duplicate ids,
+    /// broken reference nodes and markings of transitions and reference nodes, so that
+    /// [`PNMLDocument::validate`] can report all of them. Only the last object with an id
+    /// can be found by it. Fails only if the document cannot be read at all.
+    pub fn from_reader_lenient<R: Read>(source: R) -> Result<Self> {
+        PNMLDocument::read(source, true)
+    }
+
+    fn read<R: Read>(source: R, lenient: bool) -> Result<Self> {
+        let mut reader = PnmlReader {
+            events: EventReader::new(source),
+            lenient,
+        };
+        let mut document = PNMLDocument::new();
+        match reader.next_child()? {
+            Some((name, _)) if name.local_name == "pnml" => {}
+            _ => return Err(PetriError::invalid(None, "missing pnml root element")),
+        }
+        while let Some((name, attributes)) = reader.next_child()? {
+            match name.local_name.as_str() {
+                "net" => {
+                    let net = reader
+                        .read_net(&attributes, &document.ids)
+                        .map_err(context(Operation::ReadNet, &attributes))?;
+                    document.petri_nets.push(net);
+                }
+                _ => reader.skip()?,
+            }
+        }
+        Ok(document)
+    }
+}
+
+/// Recursive descent over the xml events of a pnml document.
+///
+/// Every `read_*` function is called right after the start element it reads
+/// and consumes everything up to and including the matching end element.
+struct PnmlReader<R: Read> {
+    events: EventReader<R>,
+    /// keeps inconsistencies for [`PNMLDocument::validate`] instead of failing
+    lenient: bool,
+}
+
+impl<R: Read> PnmlReader<R> {
+    /// Returns the next child element of the current element
+    /// or None if the end of the current element is reached.
+    /// Text between elements is ignored.
+    fn next_child(&mut self) -> Result<Option<(OwnedName, Vec<OwnedAttribute>)>> {
+        loop {
+            match self.events.next()? {
+                ReaderEvent::StartElement {
+                    name, attributes, ..
+                } => return Ok(Some((name, attributes))),
+                ReaderEvent::EndElement { .. } => return Ok(None),
+                ReaderEvent::EndDocument => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+
+    /// registers an id read from the document, a duplicate is kept if reading leniently
+    fn register(&self, ids: &IdRegistry, id: &str) -> Result<PNMLID> {
+        match ids.register(id) {
+            Err(PetriError::DuplicateId { .. }) if self.lenient => Ok(PNMLID::new(id)),
+            result => result,
+        }
+    }
+
+    /// skips the rest of the current element
+    fn skip(&mut self) -> Result<()> {
+        self.events.skip()?;
+        Ok(())
+    }
+
+    /// reads the content of a `<text>` element
+    fn read_text(&mut self) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            match self.events.next()? {
+                ReaderEvent::Characters(chars) | ReaderEvent::CData(chars) => text.push_str(&chars),
+                ReaderEvent::Whitespace(chars) => text.push_str(&chars),
+                ReaderEvent::StartElement { name, .. } => {
+                    return Err(PetriError::invalid(
+                        None,
+                        format!("unexpected element {} in text", name.local_name),
+                    ))
+                }
+                ReaderEvent::EndElement { .. } | ReaderEvent::EndDocument => return Ok(text),
+                _ => {}
+            }
+        }
+    }
+
+    /// reads the content of a `<toolspecific>` element without interpreting it
+    fn read_tool_specific(&mut self, attributes: &[OwnedAttribute]) -> Result<ToolInfo> {
+        let tool = attribute(attributes, "tool", "toolspecific")?;
+        let version = attribute(attributes, "version", "toolspecific")?;
+        let content = read_raw_content(&mut self.events)?;
+        Ok(ToolInfo::from_events(tool, version, content))
+    }
+
+    /// keeps a label this library does not know as it is
+    fn read_unknown_label(&mut self, element: &str) -> Result<LabelBase> {
+        Ok(LabelBase::new(Label::Unknown {
+            element: element.into(),
+            content: read_raw_content(&mut self.events)?,
+        }))
+    }
+
+    /// reads the `<text>`, `<graphics>` and `<toolspecific>` children of a label
+    /// like `<name>` or `<initialMarking>`
+    fn read_annotation(
+        &mut self,
+    ) -> Result<(Option<String>, Option<AnnotationGraphics>, Vec<ToolInfo>)> {
+        let mut text = None;
+        let mut graphics = None;
+        let mut tool_specific = Vec::new();
+        while let Some((name, attributes)) = self.next_child()? {
+            match name.local_name.as_str() {
+                "text" => text = Some(self.read_text()?),
+                "graphics" => graphics = Some(self.read_annotation_graphics()?),
+                "toolspecific" => tool_specific.push(self.read_tool_specific(&attributes)?),
+                _ => self.skip()?,
+            }
+        }
+        Ok((text, graphics, tool_specific))
+    }
+
+    /// reads a label whose text is a number like `<initialMarking>` or `<inscription>`
+    #[allow(clippy::type_complexity)]
+    fn read_number_label(
+        &mut self,
+        label: &str,
+    ) -> Result<(usize, Option<AnnotationGraphics>, Vec<ToolInfo>)> {
+        let (text, graphics, tool_specific) = self.read_annotation()?;
+        let text =
+            text.ok_or_else(|| PetriError::invalid(None, format!("{} without text", label)))?;
+        let number = text.trim().parse().map_err(|_| {
+            PetriError::invalid(None, format!("{} is no valid {}", text.trim(), label))
+        })?;
+        Ok((number, graphics, tool_specific))
+    }
+
+    fn read_name(&mut self) -> Result<PNMLName> {
+        let (text, graphics, tool_specific) = self.read_annotation()?;
+        Ok(PNMLName {
+            text,
+            graphics,
+            tool_specific,
+        })
+    }
+
+    fn read_node_graphics(&mut self) -> Result<Graphics> {
+        let mut graphics = NodeGraphics::default();
+        while let Some((name, attributes)) = self.next_child()? {
+            match name.local_name.as_str() {
+                "position" => graphics.position = coordinate(&attributes, "position")?,
+                "dimension" => graphics.dimension = Some(coordinate(&attributes, "dimension")?),
+                "fill" => graphics.fill = Some(fill(&attributes)?),
+                "line" => graphics.line = Some(line(&attributes)?),
+                _ => {}
+            }
+            self.skip()?;
+        }
+        Ok(Graphics::Node(graphics))
+    }
+
+    fn read_arc_graphics(&mut self) -> Result<Graphics> {
+        let mut graphics = ArcGraphics::default();
+        while let Some((name, attributes)) = self.next_child()? {
+            match name.local_name.as_str() {
+                "position" => graphics
+                    .positions
+                    .push(coordinate(&attributes, "position")?),
+                "line" => graphics.line = Some(line(&attributes)?),
+                _ => {}
+            }
+            self.skip()?;
+        }
+        Ok(Graphics::Arc(graphics))
+    }
+
+    fn read_annotation_graphics(&mut self) -> Result<AnnotationGraphics> {
+        let mut graphics = AnnotationGraphics::default();
+        while let Some((name, attributes)) = self.next_child()? {
+            match name.local_name.as_str() {
+                "offset" => graphics.offset = coordinate(&attributes, "offset")?,
+                "fill" => graphics.fill = Some(fill(&attributes)?),
+                "line" => graphics.line = Some(line(&attributes)?),
+                "font" => graphics.font = Some(font(&attributes)?),
+                _ => {}
+            }
+            self.skip()?;
+        }
+        Ok(graphics)
+    }
+
+    /// the ids of the net are registered in `ids`
+    fn read_net(&mut self, attributes: &[OwnedAttribute], ids: &IdRegistry) -> Result<PetriNet> {
+        let id = self.register(ids, attribute(attributes, "id", "net")?)?;
+        let mut net = PetriNet::new(id, ids.clone());
+        net.typ = attribute(attributes, "type", "net")?.parse()?;
+        while let Some((name, attributes)) = self.next_child()? {
+            match name.local_name.as_str() {
+                "name" => net.name = self.read_name()?,
+                "toolspecific" => net
+                    .tool_specific
+                    .push(self.read_tool_specific(&attributes)?),
+                "page" => {
+                    self.read_page(&attributes, &mut net, None)
+                        .map_err(context(Operation::ReadPage, &attributes))?;
+                }
+                element => net.labels.push(self.read_unknown_label(element)?),
+            }
+        }
+        if !self.lenient {
+            net.check_reference_nodes()?;
+        }
+        Ok(net)
+    }
+
+    /// reads the page and everything on it into the net
+    fn read_page(
+        &mut self,
+        attributes: &[OwnedAttribute],
+        net: &mut PetriNet,
+        parent: Option<&PageRef>,
+    ) -> Result<()> {
+        let id = self.register(&net.ids, attribute(attributes, "id", "page")?)?;
+        let page_ref = net.insert_page(
+            ObjectBase::new(id, None.into(), Object::Page(Page::new())),
+            parent,
+        )?;
+        while let Some((name, attributes)) = self.next_child()? {
+            let object = match name.local_name.as_str() {
+                "place" => self
+                    .read_node(&attributes, "place", Node::Place)
+                    .map_err(context(Operation::ReadNode, &attributes))?,
+                "transition" => self
+                    .read_node(&attributes, "transition", Node::Transition)
+                    .map_err(context(Operation::ReadNode, &attributes))?,
+                // the linked nodes are checked when the whole net is read
+                "referencePlace" => {
+                    let target = PNMLID::new(attribute(&attributes, "ref", "referencePlace")?);
+                    self.read_node(&attributes, "referencePlace", Node::PlaceRef(target))
+                        .map_err(context(Operation::ReadNode, &attributes))?
+                }
+                "referenceTransition" => {
+                    let target = PNMLID::new(attribute(&attributes, "ref", "referenceTransition")?);
+                    let node = Node::TransitionRef(target);
+                    self.read_node(&attributes, "referenceTransition", node)
+                        .map_err(context(Operation::ReadNode, &attributes))?
+                }
+                "arc" => self
+                    .read_arc(&attributes)
+                    .map_err(context(Operation::ReadArc, &attributes))?,
+                "page" => {
+                    self.read_page(&attributes, net, Some(&page_ref))
+                        .map_err(context(Operation::ReadPage, &attributes))?;
+                    continue;
+                }
+                "name" => {
+                    net.get_page_mut(&page_ref)?.name = self.read_name()?;
+                    continue;
+                }
+                "graphics" => {
+                    net.get_page_mut(&page_ref)?.graphics = Some(self.read_node_graphics()?);
+                    continue;
+                }
+                "toolspecific" => {
+                    let info = self.read_tool_specific(&attributes)?;
+                    net.get_page_mut(&page_ref)?.tool_specific.push(info);
+                    continue;
+                }
+                element => {
+                    let label = self.read_unknown_label(element)?;
+                    let base = net.get_page_mut(&page_ref)?;
+                    base.labels.get_or_insert_with(Vec::new).push(label);
+                    continue;
+                }
+            };
+            self.register(&net.ids, &object.id.0)?;
+            net.insert_object(&page_ref, object)?;
+        }
+        Ok(())
+    }
+
+    fn read_node(
+        &mut self,
+        attributes: &[OwnedAttribute],
+        element: &str,
+        node: Node,
+    ) -> Result<ObjectBase> {
+        let mut base = ObjectBase::new(
+            PNMLID::new(attribute(attributes, "id", element)?),
+            None.into(),
+            Object::Node(node),
+        );
+        while let Some((name, child_attributes)) = self.next_child()? {
+            match name.local_name.as_str() {
+                "name" => base.name = self.read_name()?,
+                "graphics" => base.graphics = Some(self.read_node_graphics()?),
+                "toolspecific" => base
+                    .tool_specific
+                    .push(self.read_tool_specific(&child_attributes)?),
+                "initialMarking" if element == "place" || self.lenient => {
+                    let (marking, graphics, tool_specific) =
+                        self.read_number_label("initialMarking")?;
+                    base.labels.get_or_insert_with(Vec::new).push(LabelBase {
+                        label: Label::PTMarking(marking),
+                        graphics,
+                        tool_specific,
+                    });
+                }
+                _ => self.skip()?,
+            }
+        }
+        Ok(base)
+    }
+
+    fn read_arc(&mut self, attributes: &[OwnedAttribute]) -> Result<ObjectBase> {
+        let mut base = ObjectBase::new(
+            PNMLID::new(attribute(attributes, "id", "arc")?),
+            None.into(),
+            Object::Arc(
+                PNMLID::new(attribute(attributes, "source", "arc")?),
+                PNMLID::new(attribute(attributes, "target", "arc")?),
+            ),
+        );
+        while let Some((name, child_attributes)) = self.next_child()? {
+            match name.local_name.as_str() {
+                "name" => base.name = self.read_name()?,
+                "graphics" => base.graphics = Some(self.read_arc_graphics()?),
+                "toolspecific" => base
+                    .tool_specific
+                    .push(self.read_tool_specific(&child_attributes)?),
+                "inscription" => {
+                    let (weight, graphics, tool_specific) =
+                        self.read_number_label("inscription")?;
+                    let weight = std::num::NonZeroUsize::new(weight)
+                        .ok_or_else(|| PetriError::invalid(None, "inscription cannot be zero"))?;
+                    base.labels.get_or_insert_with(Vec::new).push(LabelBase {
+                        label: Label::PTAnnotation(weight),
+                        graphics,
+                        tool_specific,
+                    });
+                }
+                _ => self.skip()?,
+            }
+        }
+        Ok(base)
+    }
+}
+
+/// Parses well formed xml content (elements and text) into events
+/// that can be written with [`write_raw_content`]
+pub(crate) fn parse_raw_content(content: &str) -> Result<Vec<ReaderEvent>> {
+    let wrapped = format!("<content>{}</content>", content);
+    let mut reader = EventReader::new(wrapped.as_bytes());
+    // skip to the content of the wrapping element
+    while !matches!(reader.next()?, ReaderEvent::StartElement { .. }) {}
+    read_raw_content(&mut reader)
+}
+
+/// Replays events collected by [`read_raw_content`]
+pub(crate) fn write_raw_content<T: std::io::Write>(
+    writer: &mut xml::writer::EventWriter<T>,
+    content: &[ReaderEvent],
+) -> Result<()> {
+    for event in content {
+        if let Some(event) = event.as_writer_event() {
+            writer.write(event)?;
+        }
+    }
+    Ok(())
+}
+
+/// the events as xml string without document declaration
+pub(crate) fn raw_content_to_string(content: &[ReaderEvent]) -> Result<String> {
+    let mut buffer = Vec::new();
+    let mut writer = EmitterConfig::new()
+        .write_document_declaration(false)
+        .create_writer(&mut buffer);
+    write_raw_content(&mut writer, content)?;
+    Ok(String::from_utf8(buffer).expect("Document generated non UTF-8 string"))
+}
+
+/// Collects the events of the current element up to (excluding) its end element.
+/// Whitespace between elements is dropped, the writer indents on its own.
+pub(crate) fn read_raw_content<R: Read>(events: &mut EventReader<R>) -> Result<Vec<ReaderEvent>> {
+    let mut content = Vec::new();
+    let mut depth = 0;
+    loop {
+        let event = events.next()?;
+        match event {
+            ReaderEvent::StartElement { .. } => depth += 1,
+            ReaderEvent::EndElement { .. } if depth == 0 => return Ok(content),
+            ReaderEvent::EndElement { .. } => depth -= 1,
+            ReaderEvent::EndDocument => return Ok(content),
+            ReaderEvent::Whitespace(_) => continue,
+            _ => {}
+        }
+        content.push(event);
+    }
+}
+
+/// wraps an error with the operation and the id of the element it occurred in
+fn context(
+    operation: Operation,
+    attributes: &[OwnedAttribute],
+) -> impl FnOnce(PetriError) -> PetriError {
+    let id = attributes
+        .iter()
+        .find(|attribute| attribute.name.local_name == "id")
+        .map(|attribute| PNMLID::new(&attribute.value));
+    move |error| error.context(operation, id.as_ref())
+}
+
+/// looks up a mandatory attribute of an element
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str, element: &str) -> Result<&'a str> {
+    optional_attribute(attributes, name)
+        .ok_or_else(|| PetriError::invalid(None, format!("{} without {} attribute", element, name)))
+}
+
+fn optional_attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name.local_name == name)
+        .map(|attribute| attribute.value.as_str())
+}
+
+/// parses an optional attribute with `FromStr`
+fn parsed_attribute<T: std::str::FromStr>(
+    attributes: &[OwnedAttribute],
+    name: &str,
+    element: &str,
+) -> Result<Option<T>> {
+    optional_attribute(attributes, name)
+        .map(|value| {
+            value.trim().parse().map_err(|_| {
+                PetriError::invalid(
+                    None,
+                    format!("{} is no valid {} of {}", value, name, element),
+                )
+            })
+        })
+        .transpose()
+}
+
+fn owned_attribute(attributes: &[OwnedAttribute], name: &str) -> Option<String> {
+    optional_attribute(attributes, name).map(String::from)
+}
+
+fn coordinate(attributes: &[OwnedAttribute], element: &str) -> Result<Coordinate> {
+    let component = |name| {
+        parsed_attribute(attributes, name, element)?.ok_or_else(|| {
+            PetriError::invalid(None, format!("{} without {} attribute", element, name))
+        })
+    };
+    Ok(Coordinate {
+        x: component("x")?,
+        y: component("y")?,
+    })
+}
+
+fn fill(attributes: &[OwnedAttribute]) -> Result<Fill> {
+    Ok(Fill {
+        color: owned_attribute(attributes, "color"),
+        gradient_color: owned_attribute(attributes, "gradient-color"),
+        gradient_rotation: parsed_attribute(attributes, "gradient-rotation", "fill")?,
+        image: owned_attribute(attributes, "image"),
+    })
+}
+
+fn line(attributes: &[OwnedAttribute]) -> Result<Line> {
+    Ok(Line {
+        shape: parsed_attribute(attributes, "shape", "line")?,
+        color: owned_attribute(attributes, "color"),
+        width: parsed_attribute(attributes, "width", "line")?,
+        style: parsed_attribute(attributes, "style", "line")?,
+    })
+}
+
+fn font(attributes: &[OwnedAttribute]) -> Result<Font> {
+    Ok(Font {
+        family: owned_attribute(attributes, "family"),
+        style: owned_attribute(attributes, "style"),
+        weight: owned_attribute(attributes, "weight"),
+        size: owned_attribute(attributes, "size"),
+        decoration: owned_attribute(attributes, "decoration"),
+        align: owned_attribute(attributes, "align"),
+        rotation: parsed_attribute(attributes, "rotation", "font")?,
+    })
+}
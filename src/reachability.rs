@@ -0,0 +1,168 @@
+//! The reachability graph of a place/transition net.
+//!
+//! The graph has a state for every marking reachable from the initial marking and an edge
+//! for every transition firing between them. States are numbered in the order they are
+//! found by a breadth first search, state 0 is the initial marking. Nets with many or
+//! infinitely many reachable markings are explored up to configurable [`Limits`], the
+//! graph tells if the exploration was complete.
+
+use crate::compiled::CompiledNet;
+use crate::semantics::Marking;
+use std::collections::HashMap;
+use std::mem::size_of;
+
+/// Bounds of the exploration, None means unbounded
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// the maximal number of states
+    pub max_states: Option<usize>,
+    /// The maximal memory used by states and edges in bytes.
+    /// This is an estimate, the real usage of the allocator may be higher.
+    pub max_memory: Option<usize>,
+}
+
+/// The limit that stopped an exploration
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Limit {
+    States,
+    Memory,
+    /// a place would hold more than `usize::MAX` tokens
+    Tokens,
+}
+
+/// Tells if all reachable markings are in the graph
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Exploration {
+    Complete,
+    /// The exploration stopped at the limit. The graph contains the states found so far,
+    /// the successors of the states that are not explored are missing.
+    Truncated(Limit),
+}
+
+/// An edge of the graph, firing the transition leads to the target state
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct Edge {
+    /// index of the transition in the compiled net
+    pub transition: usize,
+    pub target: usize,
+}
+
+/// The reachability graph of a [`CompiledNet`], see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct ReachabilityGraph {
+    markings: Vec<Marking>,
+    states: HashMap<Marking, usize>,
+    edges: Vec<Vec<Edge>>,
+    exploration: Exploration,
+}
+
+/// The estimated memory of a state in bytes, a marking is stored twice:
+/// in the list of states and as key of the map
+pub(crate) fn state_size(net: &CompiledNet) -> usize {
+    2 * (size_of::<Marking>() + net.place_count() * size_of::<usize>()) + size_of::<usize>()
+}
+
+impl CompiledNet {
+    /// Explores the markings reachable from the initial marking
+    pub fn reachability_graph(&self, limits: Limits) -> ReachabilityGraph {
+        ReachabilityGraph::new(self, self.initial_marking().clone(), limits)
+    }
+}
+
+impl ReachabilityGraph {
+    /// Explores the markings reachable from `initial`, which becomes state 0.
+    /// The marking must have one entry per place of the net.
+    pub fn new(net: &CompiledNet, initial: Marking, limits: Limits) -> Self {
+        let state_size = state_size(net);
+        let mut graph = ReachabilityGraph {
+            markings: Vec::new(),
+            states: HashMap::new(),
+            edges: Vec::new(),
+            exploration: Exploration::Complete,
+        };
+        graph.insert(initial);
+        let mut memory = state_size;
+        // the states are explored in the order they were found
+        while graph.edges.len() < graph.markings.len() {
+            let marking = graph.markings[graph.edges.len()].clone();
+            let mut edges = Vec::new();
+            for transition in net.enabled(&marking) {
+                let next = match net.fire(transition, &marking) {
+                    Some(next) => next,
+                    None => return graph.truncated(Limit::Tokens),
+                };
+                let is_new = !graph.states.contains_key(&next);
+                if is_new
+                    && limits
+                        .max_states
+                        .is_some_and(|max| graph.markings.len() >= max)
+                {
+                    return graph.truncated(Limit::States);
+                }
+                // the edge counts as well, a state with many edges may not fit any more
+                let needed = size_of::<Edge>() + if is_new { state_size } else { 0 };
+                if limits.max_memory.is_some_and(|max| memory + needed > max) {
+                    return graph.truncated(Limit::Memory);
+                }
+                memory += needed;
+                let target = match graph.states.get(&next) {
+                    Some(target) => *target,
+                    None => graph.insert(next),
+                };
+                edges.push(Edge { transition, target });
+            }
+            graph.edges.push(edges);
+        }
+        graph
+    }
+
+    fn insert(&mut self, marking: Marking) -> usize {
+        let state = self.markings.len();
+        self.states.insert(marking.clone(), state);
+        self.markings.push(marking);
+        state
+    }
+
+    fn truncated(mut self, limit: Limit) -> Self {
+        self.exploration = Exploration::Truncated(limit);
+        self
+    }
+
+    pub fn exploration(&self) -> Exploration {
+        self.exploration
+    }
+
+    /// true if the graph contains all reachable markings
+    pub fn is_complete(&self) -> bool {
+        self.exploration == Exploration::Complete
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.markings.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    /// the marking of the state
+    pub fn marking(&self, state: usize) -> &Marking {
+        &self.markings[state]
+    }
+
+    /// the state with the marking, None if it was not reached
+    pub fn state(&self, marking: &Marking) -> Option<usize> {
+        self.states.get(marking).copied()
+    }
+
+    /// true if all successors of the state are in the graph, always true for complete graphs
+    pub fn is_explored(&self, state: usize) -> bool {
+        state < self.edges.len()
+    }
+
+    /// The edges leaving the state, ordered by transition.
+    /// Empty for states that are not explored.
+    pub fn successors(&self, state: usize) -> &[Edge] {
+        self.edges.get(state).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
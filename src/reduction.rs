@@ -0,0 +1,482 @@
+//! Structural reduction of place/transition nets.
+//!
+//! The reduction rules of Berthelot and Murata make a net smaller without changing its
+//! liveness and boundedness. Their conditions are tightened here so that the bound of the
+//! net, the most tokens any place can hold, is kept as well, and with it safeness: a
+//! reduced net can be explored instead of the original one. Each [`ReductionRule`] tells
+//! why it keeps the bound. The rules are applied until none of them matches anymore.
+//! The result is a new document with the reduced net on one page. Places and transitions
+//! that are kept keep their ids, names and markings; [`Reduction`] tells for every other
+//! node of the original net into which node it was merged or by which rule it was removed.
+
+use crate::compiled::CompiledNet;
+use crate::*;
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+
+/// A reduction rule, the conditions are those of the simplest form of each rule
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum ReductionRule {
+    /// A transition with one input place p1 and one output place p2, where p1 has no other
+    /// output transition, moves every token of p1 to p2 eventually. The transition is
+    /// removed and p1 is merged into p2. The merged place holds the tokens of both, which
+    /// p2 holds as well once the transition has moved them, so the bound is kept.
+    SeriesPlaces,
+    /// An unmarked place p with one input transition t1 and one output transition t2,
+    /// where t2 has no other input place, lets t2 follow t1. The place is removed and t2
+    /// is merged into t1. t1 must have an input place and t2 an output place: the tokens
+    /// p can hold then show up on an output place of t2, while a source t1 or a sink t2
+    /// could make p unbounded without any other place noticing.
+    SeriesTransitions,
+    /// Two places with the same input and output arcs and the same marking always hold
+    /// the same number of tokens. The later one is merged into the other.
+    /// Places with different markings are left alone, dropping the one with more tokens
+    /// would lower the bound.
+    ParallelPlaces,
+    /// A place p is implicit if another place q starts with the same marking, changes its
+    /// tokens the same way and p needs at most as many tokens as q for every transition:
+    /// both always hold the same number of tokens and whenever q enables a transition, p
+    /// does as well. p is removed.
+    ImplicitPlaces,
+    /// A place whose tokens never change (each transition puts back what it takes) and
+    /// that holds enough tokens for all its output transitions never disables one.
+    /// It is removed, this includes marked self-loop places and isolated places.
+    /// Only a place with no more tokens than another place starts with is removed, so
+    /// the bound is kept.
+    RedundantPlaces,
+    /// A transition that takes a token from one place and puts it back does not change
+    /// the marking. It is removed, the reachable markings stay the same.
+    /// The place must be marked: an unmarked place could keep the transition dead, and
+    /// removing it would make a net live that is not. A marked place only loses its
+    /// tokens through other transitions, which are dead themselves once it stays empty.
+    SelfLoopTransitions,
+}
+
+impl ReductionRule {
+    /// all rules, those that remove a node without changing the others first
+    pub const ALL: [ReductionRule; 6] = [
+        ReductionRule::RedundantPlaces,
+        ReductionRule::SelfLoopTransitions,
+        ReductionRule::ParallelPlaces,
+        ReductionRule::ImplicitPlaces,
+        ReductionRule::SeriesPlaces,
+        ReductionRule::SeriesTransitions,
+    ];
+}
+
+/// One application of a rule, with the ids of the nodes involved
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ReductionStep {
+    pub rule: ReductionRule,
+    /// the nodes merged into `into`
+    pub merged: Vec<String>,
+    /// the node the merged nodes were merged into
+    pub into: Option<String>,
+    pub removed: Vec<String>,
+}
+
+/// What happened to a node of the original net
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum NodeFate {
+    /// the node is part of the reduced net with the same id
+    Kept,
+    /// the node was merged into the node with the id, which may have been merged itself later
+    Merged {
+        into: String,
+        rule: ReductionRule,
+    },
+    Removed(ReductionRule),
+}
+
+/// A reduced net with the explanation of the reduction, see the [module documentation](self)
+#[derive(Debug)]
+pub struct Reduction {
+    /// the document with the reduced net as only net
+    pub document: PNMLDocument,
+    pub net: PetriNetRef,
+    /// the rules applied, in order
+    pub steps: Vec<ReductionStep>,
+    fates: HashMap<String, NodeFate>,
+}
+
+impl Reduction {
+    /// what happened to the place or transition of the original net with the id
+    pub fn fate(&self, id: &str) -> Option<&NodeFate> {
+        self.fates.get(id)
+    }
+
+    /// The node of the reduced net that represents the node of the original net with the
+    /// id, following merges. None if the node was removed.
+    pub fn representative(&self, id: &str) -> Option<NodeRef> {
+        let mut id = id;
+        loop {
+            match self.fates.get(id)? {
+                NodeFate::Kept => {
+                    return match self.document.find_by_id(id) {
+                        Some(Handle::Node(node)) => Some(node),
+                        _ => None,
+                    }
+                }
+                NodeFate::Merged { into, .. } => id = into,
+                NodeFate::Removed(_) => return None,
+            }
+        }
+    }
+}
+
+impl PetriNet {
+    /// Reduces the net with the rules, which are tried in the given order after every step
+    /// until none matches. [`ReductionRule::ALL`] applies all of them.
+    /// Labels, graphics and the page structure are not carried over to the reduced net.
+    pub fn reduce(&self, rules: &[ReductionRule]) -> Result<Reduction> {
+        let compiled = self.compile()?;
+        let mut reducer = Reducer::new(&compiled);
+        let mut steps = Vec::new();
+        while let Some(step) = rules.iter().find_map(|rule| reducer.apply(*rule)) {
+            steps.push(step);
+        }
+        let mut fates: HashMap<String, NodeFate> = HashMap::new();
+        for step in &steps {
+            for id in &step.merged {
+                let into = step.into.clone().unwrap_or_default();
+                let rule = step.rule;
+                fates.insert(id.clone(), NodeFate::Merged { into, rule });
+            }
+            for id in &step.removed {
+                fates.insert(id.clone(), NodeFate::Removed(step.rule));
+            }
+        }
+        let (document, net) = self.build(&compiled, &reducer)?;
+        for id in (0..compiled.place_count())
+            .filter(|p| reducer.places[*p].is_some())
+            .map(|p| compiled.place_id(p))
+            .chain(
+                (0..compiled.transition_count())
+                    .filter(|t| reducer.transitions[*t].is_some())
+                    .map(|t| compiled.transition_id(t)),
+            )
+        {
+            fates.insert(id.into(), NodeFate::Kept);
+        }
+        Ok(Reduction {
+            document,
+            net,
+            steps,
+            fates,
+        })
+    }
+
+    /// the document with the nodes and arcs that are left
+    fn build(
+        &self,
+        compiled: &CompiledNet,
+        reducer: &Reducer,
+    ) -> Result<(PNMLDocument, PetriNetRef)> {
+        let mut document = PNMLDocument::new();
+        let net_ref = document.add_petri_net_with_id(&self.id.0, self.name.text.as_deref())?;
+        let net = document.petri_net_data(net_ref)?;
+        // the id of the first page is used by no node
+        let page = match self.pages.first() {
+            Some(key) => {
+                let id = PageRef(*key).id(self)?;
+                net.add_page_with_id(id, None)?
+            }
+            None => net.add_page(None),
+        };
+        let mut places = HashMap::new();
+        for (p, place) in reducer.places.iter().enumerate() {
+            if let Some(place) = place {
+                let mut node = net.add_place_with_id(&page, compiled.place_id(p))?;
+                if let Some(name) = compiled.place(p).get_name(self)? {
+                    node.name(net, name)?;
+                }
+                if place.marking > 0 {
+                    node.initial_marking(net, place.marking)?;
+                }
+                places.insert(p, node);
+            }
+        }
+        for (t, transition) in reducer.transitions.iter().enumerate() {
+            if let Some(transition) = transition {
+                let mut node = net.add_transition_with_id(&page, compiled.transition_id(t))?;
+                if let Some(name) = compiled.transition(t).get_name(self)? {
+                    node.name(net, name)?;
+                }
+                for (p, weight) in &transition.pre {
+                    let mut arc = net.add_arc(&page, &places[p], &node)?;
+                    if *weight > 1 {
+                        arc.inscription(net, weight_of(*weight))?;
+                    }
+                }
+                for (p, weight) in &transition.post {
+                    let mut arc = net.add_arc(&page, &node, &places[p])?;
+                    if *weight > 1 {
+                        arc.inscription(net, weight_of(*weight))?;
+                    }
+                }
+            }
+        }
+        Ok((document, net_ref))
+    }
+}
+
+fn weight_of(weight: usize) -> NonZeroUsize {
+    NonZeroUsize::new(weight).expect("arcs without weight are not stored")
+}
+
+/// the arcs of a node by the index of the node at the other end, with their weights
+type Arcs = BTreeMap<usize, usize>;
+
+#[derive(Debug, Clone)]
+struct PlaceData {
+    marking: usize,
+    /// the transitions producing on the place
+    pre: Arcs,
+    /// the transitions consuming from the place
+    post: Arcs,
+}
+
+#[derive(Debug, Clone)]
+struct TransitionData {
+    pre: Arcs,
+    post: Arcs,
+}
+
+/// The net during the reduction, removed nodes are None
+struct Reducer {
+    place_ids: Vec<String>,
+    transition_ids: Vec<String>,
+    places: Vec<Option<PlaceData>>,
+    transitions: Vec<Option<TransitionData>>,
+}
+
+impl Reducer {
+    fn new(net: &CompiledNet) -> Self {
+        let marking = net.initial_marking();
+        Reducer {
+            place_ids: (0..net.place_count())
+                .map(|p| net.place_id(p).into())
+                .collect(),
+            transition_ids: (0..net.transition_count())
+                .map(|t| net.transition_id(t).into())
+                .collect(),
+            places: (0..net.place_count())
+                .map(|p| {
+                    Some(PlaceData {
+                        marking: marking[p],
+                        pre: net.place_pre(p).collect(),
+                        post: net.place_post(p).collect(),
+                    })
+                })
+                .collect(),
+            transitions: (0..net.transition_count())
+                .map(|t| {
+                    Some(TransitionData {
+                        pre: net.pre(t).collect(),
+                        post: net.post(t).collect(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// applies the rule at the first place it matches
+    fn apply(&mut self, rule: ReductionRule) -> Option<ReductionStep> {
+        match rule {
+            ReductionRule::SeriesPlaces => self.series_places(),
+            ReductionRule::SeriesTransitions => self.series_transitions(),
+            ReductionRule::ParallelPlaces => self.parallel_places(),
+            ReductionRule::ImplicitPlaces => self.implicit_places(),
+            ReductionRule::RedundantPlaces => self.redundant_places(),
+            ReductionRule::SelfLoopTransitions => self.self_loop_transitions(),
+        }
+    }
+
+    fn places(&self) -> impl Iterator<Item = (usize, &PlaceData)> {
+        self.places
+            .iter()
+            .enumerate()
+            .filter_map(|(p, place)| Some((p, place.as_ref()?)))
+    }
+
+    fn transitions(&self) -> impl Iterator<Item = (usize, &TransitionData)> {
+        self.transitions
+            .iter()
+            .enumerate()
+            .filter_map(|(t, transition)| Some((t, transition.as_ref()?)))
+    }
+
+    fn add_output(&mut self, transition: usize, place: usize, weight: usize) {
+        if let (Some(t), Some(p)) = (&mut self.transitions[transition], &mut self.places[place]) {
+            *t.post.entry(place).or_default() += weight;
+            *p.pre.entry(transition).or_default() += weight;
+        }
+    }
+
+    fn remove_place(&mut self, place: usize) {
+        if let Some(data) = self.places[place].take() {
+            for t in data.pre.keys().chain(data.post.keys()) {
+                if let Some(transition) = &mut self.transitions[*t] {
+                    transition.pre.remove(&place);
+                    transition.post.remove(&place);
+                }
+            }
+        }
+    }
+
+    fn remove_transition(&mut self, transition: usize) {
+        if let Some(data) = self.transitions[transition].take() {
+            for p in data.pre.keys().chain(data.post.keys()) {
+                if let Some(place) = &mut self.places[*p] {
+                    place.pre.remove(&transition);
+                    place.post.remove(&transition);
+                }
+            }
+        }
+    }
+
+    fn step(&self, rule: ReductionRule) -> ReductionStep {
+        ReductionStep {
+            rule,
+            merged: Vec::new(),
+            into: None,
+            removed: Vec::new(),
+        }
+    }
+
+    fn series_places(&mut self) -> Option<ReductionStep> {
+        let (t, first, second, marking) = self.transitions().find_map(|(t, transition)| {
+            let (first, second) = (single(&transition.pre)?, single(&transition.post)?);
+            let (input, output) = (self.places[first].as_ref()?, self.places[second].as_ref()?);
+            // the merged place holds the tokens of both
+            let marking = input.marking.checked_add(output.marking)?;
+            let only_output = single(&input.post) == Some(t);
+            (first != second && only_output).then_some((t, first, second, marking))
+        })?;
+        let moved = self.places[first].clone()?;
+        self.remove_transition(t);
+        self.remove_place(first);
+        if let Some(place) = &mut self.places[second] {
+            place.marking = marking;
+        }
+        for (u, weight) in moved.pre.iter().filter(|(u, _)| **u != t) {
+            self.add_output(*u, second, *weight);
+        }
+        let mut step = self.step(ReductionRule::SeriesPlaces);
+        step.merged.push(self.place_ids[first].clone());
+        step.into = Some(self.place_ids[second].clone());
+        step.removed.push(self.transition_ids[t].clone());
+        Some(step)
+    }
+
+    fn series_transitions(&mut self) -> Option<ReductionStep> {
+        let (p, first, second) = self.places().find_map(|(p, place)| {
+            let (first, second) = (single(&place.pre)?, single(&place.post)?);
+            let (input, output) = (
+                self.transitions[first].as_ref()?,
+                self.transitions[second].as_ref()?,
+            );
+            let only_input = single(&output.pre) == Some(p);
+            // a source or sink transition would hide the tokens of p
+            let observed = !input.pre.is_empty() && !output.post.is_empty();
+            (place.marking == 0 && first != second && only_input && observed)
+                .then_some((p, first, second))
+        })?;
+        let moved = self.transitions[second].clone()?;
+        self.remove_place(p);
+        self.remove_transition(second);
+        for (q, weight) in &moved.post {
+            self.add_output(first, *q, *weight);
+        }
+        let mut step = self.step(ReductionRule::SeriesTransitions);
+        step.merged.push(self.transition_ids[second].clone());
+        step.into = Some(self.transition_ids[first].clone());
+        step.removed.push(self.place_ids[p].clone());
+        Some(step)
+    }
+
+    fn parallel_places(&mut self) -> Option<ReductionStep> {
+        let (kept, removed) = self.places().find_map(|(p, place)| {
+            self.places()
+                .filter(|(q, _)| *q > p)
+                .find(|(_, other)| {
+                    place.pre == other.pre
+                        && place.post == other.post
+                        && place.marking == other.marking
+                })
+                .map(|(q, _)| (p, q))
+        })?;
+        self.remove_place(removed);
+        let mut step = self.step(ReductionRule::ParallelPlaces);
+        step.merged.push(self.place_ids[removed].clone());
+        step.into = Some(self.place_ids[kept].clone());
+        Some(step)
+    }
+
+    fn implicit_places(&mut self) -> Option<ReductionStep> {
+        let p = self.places().find_map(|(p, place)| {
+            self.places()
+                .any(|(q, other)| q != p && is_implied(place, other))
+                .then_some(p)
+        })?;
+        self.remove_place(p);
+        let mut step = self.step(ReductionRule::ImplicitPlaces);
+        step.removed.push(self.place_ids[p].clone());
+        Some(step)
+    }
+
+    fn redundant_places(&mut self) -> Option<ReductionStep> {
+        let p = self.places().find_map(|(p, place)| {
+            let needed = place.post.values().copied().max().unwrap_or(0);
+            // the other place reaches the tokens of p, so dropping p keeps the bound
+            let covered = place.marking == 0
+                || self
+                    .places()
+                    .any(|(q, other)| q != p && other.marking >= place.marking);
+            (place.pre == place.post && place.marking >= needed && covered).then_some(p)
+        })?;
+        self.remove_place(p);
+        let mut step = self.step(ReductionRule::RedundantPlaces);
+        step.removed.push(self.place_ids[p].clone());
+        Some(step)
+    }
+
+    fn self_loop_transitions(&mut self) -> Option<ReductionStep> {
+        let t = self.transitions().find_map(|(t, transition)| {
+            let marked = self.places[single(&transition.pre)?].as_ref()?.marking > 0;
+            (marked && transition.pre == transition.post).then_some(t)
+        })?;
+        self.remove_transition(t);
+        let mut step = self.step(ReductionRule::SelfLoopTransitions);
+        step.removed.push(self.transition_ids[t].clone());
+        Some(step)
+    }
+}
+
+/// the node at the other end of the only arc, which must have the weight one
+fn single(arcs: &Arcs) -> Option<usize> {
+    match arcs.iter().next() {
+        Some((node, 1)) if arcs.len() == 1 => Some(*node),
+        _ => None,
+    }
+}
+
+/// true if `place` enables every transition `other` enables, see [`ReductionRule::ImplicitPlaces`]
+fn is_implied(place: &PlaceData, other: &PlaceData) -> bool {
+    let change = |data: &PlaceData, t: &usize| {
+        let produced = data.pre.get(t).copied().unwrap_or(0) as i128;
+        produced - data.post.get(t).copied().unwrap_or(0) as i128
+    };
+    let same_change = place
+        .pre
+        .keys()
+        .chain(place.post.keys())
+        .chain(other.pre.keys())
+        .chain(other.post.keys())
+        .all(|t| change(place, t) == change(other, t));
+    let needs_less = place
+        .post
+        .iter()
+        .all(|(t, weight)| other.post.get(t).is_some_and(|needed| weight <= needed));
+    same_change && needs_less && place.marking == other.marking
+}
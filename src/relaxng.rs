@@ -0,0 +1,1062 @@
+//! A RELAX NG validator, just big enough for the grammars embedded in `grammars/`.
+//!
+//! Documents are matched with pattern derivatives as described by James Clark in
+//! "An algorithm for RELAX NG validation": the pattern that is left after reading an
+//! attribute, text or child element is computed from the one before, and an element is
+//! valid if the pattern left after its content accepts the empty sequence.
+//! Unlike the original algorithm the document is read as a tree, so a child element that
+//! does not fit is reported with its path and skipped instead of stopping the validation.
+//!
+//! Supported are the patterns of the full syntax except `list`, `data` with `except`,
+//! nested grammars and external references. Datatypes are the built-in ones and the
+//! subset of XML Schema datatypes used by the grammars.
+
+use crate::grammar_check::Violation;
+use crate::pnml::is_xml_name;
+use crate::{PetriError, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::rc::Rc;
+use xml::reader::{EventReader, XmlEvent};
+
+const RNG_NS: &str = "http://relaxng.org/ns/structure/1.0";
+const XSD_LIBRARY: &str = "http://www.w3.org/2001/XMLSchema-datatypes";
+
+/// An element of a parsed xml document with its namespace resolved
+#[derive(Debug)]
+pub(crate) struct XmlElement {
+    namespace: String,
+    name: String,
+    /// namespace, local name and value
+    attributes: Vec<(String, String, String)>,
+    children: Vec<XmlChild>,
+}
+
+#[derive(Debug)]
+enum XmlChild {
+    Element(XmlElement),
+    Text(String),
+}
+
+impl XmlElement {
+    /// reads the root element of the document
+    pub(crate) fn parse<R: Read>(source: R) -> Result<XmlElement> {
+        let mut stack: Vec<XmlElement> = Vec::new();
+        for event in EventReader::new(source) {
+            match event? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(XmlElement {
+                    namespace: name.namespace.unwrap_or_default(),
+                    name: name.local_name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|a| {
+                            (
+                                a.name.namespace.unwrap_or_default(),
+                                a.name.local_name,
+                                a.value,
+                            )
+                        })
+                        .collect(),
+                    children: Vec::new(),
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let element = stack
+                        .pop()
+                        .ok_or_else(|| PetriError::invalid(None, "unbalanced end element"))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(XmlChild::Element(element)),
+                        None => return Ok(element),
+                    }
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text) => {
+                    if let Some(parent) = stack.last_mut() {
+                        match parent.children.last_mut() {
+                            Some(XmlChild::Text(before)) => before.push_str(&text),
+                            _ => parent.children.push(XmlChild::Text(text)),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(PetriError::invalid(
+            None,
+            "the document has no root element",
+        ))
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(namespace, local, _)| namespace.is_empty() && local == name)
+            .map(|(_, _, value)| value.as_str())
+    }
+
+    /// the child elements in the RELAX NG namespace, annotations are skipped
+    fn rng_children(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlChild::Element(element) if element.namespace == RNG_NS => Some(element),
+            _ => None,
+        })
+    }
+
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                XmlChild::Text(text) => Some(text.as_str()),
+                XmlChild::Element(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+enum NameClass {
+    Name(String, String),
+    AnyName(Option<Box<NameClass>>),
+    NsName(String, Option<Box<NameClass>>),
+    Choice(Box<NameClass>, Box<NameClass>),
+}
+
+impl NameClass {
+    fn contains(&self, namespace: &str, name: &str) -> bool {
+        let excluded = |except: &Option<Box<NameClass>>| {
+            except
+                .as_ref()
+                .is_some_and(|except| except.contains(namespace, name))
+        };
+        match self {
+            NameClass::Name(ns, local) => ns == namespace && local == name,
+            NameClass::AnyName(except) => !excluded(except),
+            NameClass::NsName(ns, except) => ns == namespace && !excluded(except),
+            NameClass::Choice(a, b) => a.contains(namespace, name) || b.contains(namespace, name),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            NameClass::Name(_, local) => local.clone(),
+            NameClass::AnyName(_) => "any name".into(),
+            NameClass::NsName(ns, _) => format!("any name in {}", ns),
+            NameClass::Choice(a, b) => format!("{} or {}", a.describe(), b.describe()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Kind {
+    String,
+    Token,
+    Boolean,
+    Decimal,
+    Double,
+    Integer,
+    NonNegativeInteger,
+    PositiveInteger,
+    AnyUri,
+    NCName,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Facet {
+    MinInclusive(f64),
+    MinExclusive(f64),
+    MaxInclusive(f64),
+    MaxExclusive(f64),
+}
+
+#[derive(Debug)]
+struct Datatype {
+    kind: Kind,
+    facets: Vec<Facet>,
+}
+
+impl Datatype {
+    fn new(library: &str, name: &str) -> Result<Self> {
+        let kind = match (library, name) {
+            ("", "string") | (XSD_LIBRARY, "string") => Kind::String,
+            ("", "token") | (XSD_LIBRARY, "token") => Kind::Token,
+            (XSD_LIBRARY, "boolean") => Kind::Boolean,
+            (XSD_LIBRARY, "decimal") => Kind::Decimal,
+            (XSD_LIBRARY, "double") | (XSD_LIBRARY, "float") => Kind::Double,
+            (XSD_LIBRARY, "integer") => Kind::Integer,
+            (XSD_LIBRARY, "nonNegativeInteger") => Kind::NonNegativeInteger,
+            (XSD_LIBRARY, "positiveInteger") => Kind::PositiveInteger,
+            (XSD_LIBRARY, "anyURI") => Kind::AnyUri,
+            (XSD_LIBRARY, "NCName") | (XSD_LIBRARY, "ID") | (XSD_LIBRARY, "IDREF") => Kind::NCName,
+            _ => {
+                return Err(PetriError::invalid(
+                    None,
+                    format!("unsupported datatype {} of library '{}'", name, library),
+                ))
+            }
+        };
+        Ok(Datatype {
+            kind,
+            facets: Vec::new(),
+        })
+    }
+
+    fn allows(&self, text: &str) -> bool {
+        let value = collapse(text);
+        let integer = |value: &str| {
+            let digits = value.strip_prefix(&['+', '-'][..]).unwrap_or(value);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        };
+        let lexical = match self.kind {
+            Kind::String | Kind::Token | Kind::AnyUri => true,
+            Kind::Boolean => matches!(value.as_str(), "true" | "false" | "1" | "0"),
+            Kind::Decimal => {
+                let unsigned = value.strip_prefix(&['+', '-'][..]).unwrap_or(&value);
+                let mut parts = unsigned.splitn(2, '.');
+                let whole = parts.next().unwrap_or("");
+                let fraction = parts.next().unwrap_or("");
+                !(whole.is_empty() && fraction.is_empty())
+                    && whole
+                        .chars()
+                        .chain(fraction.chars())
+                        .all(|c| c.is_ascii_digit())
+            }
+            Kind::Double => {
+                matches!(value.as_str(), "INF" | "-INF" | "NaN")
+                    || (value.parse::<f64>().is_ok()
+                        && value
+                            .chars()
+                            .all(|c| !c.is_alphabetic() || c == 'e' || c == 'E'))
+            }
+            Kind::Integer => integer(&value),
+            Kind::NonNegativeInteger => {
+                integer(&value) && (!value.starts_with('-') || value[1..].chars().all(|c| c == '0'))
+            }
+            Kind::PositiveInteger => {
+                integer(&value)
+                    && !value.starts_with('-')
+                    && value.chars().any(|c| ('1'..='9').contains(&c))
+            }
+            Kind::NCName => is_xml_name(&value),
+        };
+        lexical
+            && self.facets.iter().all(|facet| {
+                let number = match value.parse::<f64>() {
+                    Ok(number) => number,
+                    Err(_) => return false,
+                };
+                match *facet {
+                    Facet::MinInclusive(limit) => number >= limit,
+                    Facet::MinExclusive(limit) => number > limit,
+                    Facet::MaxInclusive(limit) => number <= limit,
+                    Facet::MaxExclusive(limit) => number < limit,
+                }
+            })
+    }
+
+    fn equal(&self, a: &str, b: &str) -> bool {
+        match self.kind {
+            Kind::String => a == b,
+            _ => collapse(a) == collapse(b),
+        }
+    }
+}
+
+/// the value of a token: leading, trailing and repeated whitespace removed
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug)]
+enum Pattern {
+    Empty,
+    NotAllowed,
+    Text,
+    Choice(Rc<Pattern>, Rc<Pattern>),
+    Interleave(Rc<Pattern>, Rc<Pattern>),
+    Group(Rc<Pattern>, Rc<Pattern>),
+    OneOrMore(Rc<Pattern>),
+    Attribute(Rc<NameClass>, Rc<Pattern>),
+    Element(Rc<NameClass>, Rc<Pattern>),
+    /// index of a define of the schema
+    Ref(usize),
+    Data(Rc<Datatype>),
+    Value(Rc<Datatype>, String),
+}
+
+fn empty() -> Rc<Pattern> {
+    Rc::new(Pattern::Empty)
+}
+
+fn not_allowed() -> Rc<Pattern> {
+    Rc::new(Pattern::NotAllowed)
+}
+
+fn is_not_allowed(p: &Pattern) -> bool {
+    matches!(p, Pattern::NotAllowed)
+}
+
+// the constructors simplify the patterns, so they do not grow with every derivative
+
+fn choice(a: Rc<Pattern>, b: Rc<Pattern>) -> Rc<Pattern> {
+    match (&*a, &*b) {
+        (Pattern::NotAllowed, _) => b,
+        (_, Pattern::NotAllowed) => a,
+        (Pattern::Empty, Pattern::Empty) => a,
+        _ if Rc::ptr_eq(&a, &b) => a,
+        _ => Rc::new(Pattern::Choice(a, b)),
+    }
+}
+
+fn group(a: Rc<Pattern>, b: Rc<Pattern>) -> Rc<Pattern> {
+    match (&*a, &*b) {
+        (Pattern::NotAllowed, _) | (_, Pattern::NotAllowed) => not_allowed(),
+        (Pattern::Empty, _) => b,
+        (_, Pattern::Empty) => a,
+        _ => Rc::new(Pattern::Group(a, b)),
+    }
+}
+
+fn interleave(a: Rc<Pattern>, b: Rc<Pattern>) -> Rc<Pattern> {
+    match (&*a, &*b) {
+        (Pattern::NotAllowed, _) | (_, Pattern::NotAllowed) => not_allowed(),
+        (Pattern::Empty, _) => b,
+        (_, Pattern::Empty) => a,
+        _ => Rc::new(Pattern::Interleave(a, b)),
+    }
+}
+
+fn one_or_more(p: Rc<Pattern>) -> Rc<Pattern> {
+    match &*p {
+        Pattern::NotAllowed | Pattern::Empty => p,
+        _ => Rc::new(Pattern::OneOrMore(p)),
+    }
+}
+
+/// the namespace and datatype library inherited from the ancestors of a grammar element
+#[derive(Copy, Clone)]
+struct Context<'a> {
+    ns: &'a str,
+    library: &'a str,
+}
+
+impl<'a> Context<'a> {
+    fn enter(self, element: &'a XmlElement) -> Self {
+        Context {
+            ns: element.attribute("ns").unwrap_or(self.ns),
+            library: element.attribute("datatypeLibrary").unwrap_or(self.library),
+        }
+    }
+}
+
+/// a `start` (without name) or `define` of a grammar
+struct Component<'a> {
+    name: Option<&'a str>,
+    combine: Option<&'a str>,
+    body: &'a XmlElement,
+    context: Context<'a>,
+}
+
+/// A grammar compiled to patterns, clones share the patterns
+#[derive(Debug, Clone)]
+pub(crate) struct Schema {
+    start: Rc<Pattern>,
+    defines: Vec<Rc<Pattern>>,
+}
+
+impl Schema {
+    /// Compiles the grammar in the file `start` of `files`.
+    /// Includes are resolved by the last segment of their href in `files`.
+    pub(crate) fn compile(files: &[(&str, &str)], start: &str) -> Result<Schema> {
+        let mut grammars = HashMap::new();
+        for (name, content) in files {
+            grammars.insert(*name, XmlElement::parse(content.as_bytes())?);
+        }
+        let root = Context {
+            ns: "",
+            library: "",
+        };
+        let grammar = file(&grammars, start)?;
+        let mut components = Vec::new();
+        collect_components(&grammars, grammar, root.enter(grammar), &mut components)?;
+
+        let mut names: Vec<Option<&str>> = Vec::new();
+        for component in &components {
+            if !names.contains(&component.name) {
+                names.push(component.name);
+            }
+        }
+        let index: HashMap<&str, usize> = names
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(i, name)| (*name, i))
+            .collect();
+        let compiler = Compiler { index: &index };
+        let mut start = None;
+        let mut defines = vec![not_allowed(); index.len()];
+        for name in names {
+            let parts: Vec<&Component> = components.iter().filter(|c| c.name == name).collect();
+            let combine = parts.iter().find_map(|c| c.combine);
+            if parts.iter().filter(|c| c.combine.is_none()).count() > 1 {
+                return Err(grammar_error(format!(
+                    "{} is defined more than once without combine",
+                    name.unwrap_or("start")
+                )));
+            }
+            let mut pattern: Option<Rc<Pattern>> = None;
+            for part in parts {
+                let body = compiler.group(part.body, part.context)?;
+                pattern = Some(match (pattern, combine) {
+                    (None, _) => body,
+                    (Some(before), Some("interleave")) => interleave(before, body),
+                    (Some(before), Some("choice")) => choice(before, body),
+                    (Some(_), _) => return Err(grammar_error("invalid combine")),
+                });
+            }
+            let pattern = pattern.unwrap_or_else(not_allowed);
+            match name {
+                Some(name) => defines[index[name]] = pattern,
+                None => start = Some(pattern),
+            }
+        }
+        let start = start.ok_or_else(|| grammar_error("the grammar has no start"))?;
+        Ok(Schema { start, defines })
+    }
+
+    /// Checks the document and returns all violations of the grammar
+    pub(crate) fn validate(&self, root: &XmlElement) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let path = format!("/{}", root.name);
+        let (rest, valid) = self.child_deriv(&self.start, root, &path, Some(&mut violations));
+        if valid && !rest.is_some_and(|rest| self.nullable(&rest)) {
+            violations.push(Violation::new(&path, "the document is incomplete"));
+        }
+        violations
+    }
+
+    fn define(&self, index: usize) -> &Rc<Pattern> {
+        &self.defines[index]
+    }
+
+    /// Checks the attributes and content of the element against the content of an element pattern.
+    /// Violations are only reported if `report` is given, otherwise the check stops at the first one.
+    fn check_element(
+        &self,
+        element: &XmlElement,
+        content: &Rc<Pattern>,
+        path: &str,
+        mut report: Option<&mut Vec<Violation>>,
+    ) -> bool {
+        let mut valid = true;
+        let mut p = content.clone();
+        for (namespace, name, value) in &element.attributes {
+            let next = self.attribute_deriv(&p, namespace, name, value, false);
+            if !is_not_allowed(&next) {
+                p = next;
+                continue;
+            }
+            let lenient = self.attribute_deriv(&p, namespace, name, value, true);
+            let message = match is_not_allowed(&lenient) {
+                true => format!("attribute {} is not allowed here", name),
+                false => format!("invalid value '{}' of attribute {}", value, name),
+            };
+            let path = format!("{}/@{}", path, name);
+            if !record(&mut valid, &mut report, &path, message) {
+                return false;
+            }
+            if !is_not_allowed(&lenient) {
+                p = lenient;
+            }
+        }
+        let closed = self.close(&p, false);
+        if !is_not_allowed(&closed) {
+            p = closed;
+        } else {
+            let mut missing = Vec::new();
+            self.required_attributes(&p, &mut missing);
+            if !record(
+                &mut valid,
+                &mut report,
+                path,
+                format!("missing attribute {}", missing.join(", ")),
+            ) {
+                return false;
+            }
+            p = self.close(&p, true);
+        }
+
+        if element
+            .children
+            .iter()
+            .all(|child| matches!(child, XmlChild::Text(_)))
+        {
+            let text = element.text();
+            if text.trim().is_empty() {
+                p = choice(p.clone(), self.text_deriv(&p, &text, false));
+            } else if !self.consume_text(&mut p, &text, path, &mut valid, &mut report) {
+                return false;
+            }
+        } else {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for child in &element.children {
+                match child {
+                    XmlChild::Text(text) if text.trim().is_empty() => {}
+                    XmlChild::Text(text) => {
+                        if !self.consume_text(&mut p, text, path, &mut valid, &mut report) {
+                            return false;
+                        }
+                    }
+                    XmlChild::Element(child) => {
+                        let count = counts.entry(&child.name).or_insert(0);
+                        *count += 1;
+                        let child_path = format!("{}/{}[{}]", path, child.name, count);
+                        let (next, child_valid) =
+                            self.child_deriv(&p, child, &child_path, report.as_deref_mut());
+                        if let Some(next) = next {
+                            p = next;
+                        }
+                        if !child_valid {
+                            valid = false;
+                            if report.is_none() {
+                                return false;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if !self.nullable(&p) {
+            let mut missing = Vec::new();
+            self.required_content(&p, &mut missing, &mut HashSet::new());
+            record(
+                &mut valid,
+                &mut report,
+                path,
+                format!("missing {}", missing.join(", ")),
+            );
+        }
+        valid
+    }
+
+    /// Matches the text and replaces `p` with the pattern left after it.
+    /// Returns false if the text is invalid and the check can stop.
+    fn consume_text(
+        &self,
+        p: &mut Rc<Pattern>,
+        text: &str,
+        path: &str,
+        valid: &mut bool,
+        report: &mut Option<&mut Vec<Violation>>,
+    ) -> bool {
+        let next = self.text_deriv(p, text, false);
+        if !is_not_allowed(&next) {
+            *p = next;
+            return true;
+        }
+        let lenient = self.text_deriv(p, text, true);
+        let message = match is_not_allowed(&lenient) {
+            true => format!("text '{}' is not allowed here", text.trim()),
+            false => format!("invalid text '{}'", text.trim()),
+        };
+        if !is_not_allowed(&lenient) {
+            *p = lenient;
+        }
+        record(valid, report, path, message)
+    }
+
+    /// The pattern left after the child element and if the child is valid.
+    /// If the child does not fit at all, no pattern is returned and the child is skipped.
+    fn child_deriv(
+        &self,
+        p: &Rc<Pattern>,
+        child: &XmlElement,
+        path: &str,
+        report: Option<&mut Vec<Violation>>,
+    ) -> (Option<Rc<Pattern>>, bool) {
+        let mut candidates = Vec::new();
+        self.candidates(p, child, &mut candidates, &mut HashSet::new());
+        if candidates.is_empty() {
+            if let Some(violations) = report {
+                let message = format!("element {} is not allowed here", child.name);
+                violations.push(Violation::new(path, message));
+            }
+            return (None, false);
+        }
+        let accepted: Vec<Rc<Pattern>> = candidates
+            .iter()
+            .filter(|content| self.check_element(child, content, path, None))
+            .cloned()
+            .collect();
+        if accepted.is_empty() {
+            return match report {
+                Some(violations) => {
+                    // report the problems inside the child and go on as if it was valid
+                    self.check_element(child, &candidates[0], path, Some(violations));
+                    let next = self.element_deriv(p, child, &candidates[..1]);
+                    (Some(next), false)
+                }
+                None => (None, false),
+            };
+        }
+        (Some(self.element_deriv(p, child, &accepted)), true)
+    }
+
+    /// the contents of the element patterns that can match the element next
+    fn candidates(
+        &self,
+        p: &Rc<Pattern>,
+        element: &XmlElement,
+        candidates: &mut Vec<Rc<Pattern>>,
+        visited: &mut HashSet<usize>,
+    ) {
+        match &**p {
+            Pattern::Element(name, content)
+                if name.contains(&element.namespace, &element.name)
+                    && !candidates.iter().any(|other| Rc::ptr_eq(other, content)) =>
+            {
+                candidates.push(content.clone());
+            }
+            Pattern::Choice(a, b) | Pattern::Interleave(a, b) => {
+                self.candidates(a, element, candidates, visited);
+                self.candidates(b, element, candidates, visited);
+            }
+            Pattern::Group(a, b) => {
+                self.candidates(a, element, candidates, visited);
+                if self.nullable(a) {
+                    self.candidates(b, element, candidates, visited);
+                }
+            }
+            Pattern::OneOrMore(a) => self.candidates(a, element, candidates, visited),
+            Pattern::Ref(index) if visited.insert(*index) => {
+                self.candidates(self.define(*index), element, candidates, visited);
+            }
+            _ => {}
+        }
+    }
+
+    /// the pattern left after an element matching one of the `accepted` contents
+    fn element_deriv(
+        &self,
+        p: &Rc<Pattern>,
+        element: &XmlElement,
+        accepted: &[Rc<Pattern>],
+    ) -> Rc<Pattern> {
+        let deriv = |p| self.element_deriv(p, element, accepted);
+        match &**p {
+            Pattern::Element(name, content) => {
+                if name.contains(&element.namespace, &element.name)
+                    && accepted.iter().any(|other| Rc::ptr_eq(other, content))
+                {
+                    empty()
+                } else {
+                    not_allowed()
+                }
+            }
+            Pattern::Choice(a, b) => choice(deriv(a), deriv(b)),
+            Pattern::Interleave(a, b) => choice(
+                interleave(deriv(a), b.clone()),
+                interleave(a.clone(), deriv(b)),
+            ),
+            Pattern::Group(a, b) => {
+                let first = group(deriv(a), b.clone());
+                if self.nullable(a) {
+                    choice(first, deriv(b))
+                } else {
+                    first
+                }
+            }
+            Pattern::OneOrMore(a) => group(deriv(a), choice(p.clone(), empty())),
+            Pattern::Ref(index) => deriv(self.define(*index)),
+            _ => not_allowed(),
+        }
+    }
+
+    /// With `lenient` any text matches data and values, to go on after an invalid value.
+    fn text_deriv(&self, p: &Rc<Pattern>, text: &str, lenient: bool) -> Rc<Pattern> {
+        let deriv = |p| self.text_deriv(p, text, lenient);
+        match &**p {
+            Pattern::Text => p.clone(),
+            Pattern::Data(datatype) if lenient || datatype.allows(text) => empty(),
+            Pattern::Value(datatype, value) if lenient || datatype.equal(value, text) => empty(),
+            Pattern::Choice(a, b) => choice(deriv(a), deriv(b)),
+            Pattern::Interleave(a, b) => choice(
+                interleave(deriv(a), b.clone()),
+                interleave(a.clone(), deriv(b)),
+            ),
+            Pattern::Group(a, b) => {
+                let first = group(deriv(a), b.clone());
+                if self.nullable(a) {
+                    choice(first, deriv(b))
+                } else {
+                    first
+                }
+            }
+            Pattern::OneOrMore(a) => group(deriv(a), choice(p.clone(), empty())),
+            Pattern::Ref(index) => deriv(self.define(*index)),
+            _ => not_allowed(),
+        }
+    }
+
+    fn attribute_deriv(
+        &self,
+        p: &Rc<Pattern>,
+        namespace: &str,
+        name: &str,
+        value: &str,
+        lenient: bool,
+    ) -> Rc<Pattern> {
+        let deriv = |p| self.attribute_deriv(p, namespace, name, value, lenient);
+        match &**p {
+            Pattern::Attribute(name_class, content) => {
+                let matches = (self.nullable(content) && value.trim().is_empty())
+                    || self.nullable(&self.text_deriv(content, value, lenient));
+                if name_class.contains(namespace, name) && matches {
+                    empty()
+                } else {
+                    not_allowed()
+                }
+            }
+            Pattern::Choice(a, b) => choice(deriv(a), deriv(b)),
+            Pattern::Interleave(a, b) => choice(
+                interleave(deriv(a), b.clone()),
+                interleave(a.clone(), deriv(b)),
+            ),
+            Pattern::Group(a, b) => choice(group(deriv(a), b.clone()), group(a.clone(), deriv(b))),
+            Pattern::OneOrMore(a) => group(deriv(a), choice(p.clone(), empty())),
+            Pattern::Ref(index) => deriv(self.define(*index)),
+            _ => not_allowed(),
+        }
+    }
+
+    /// The pattern for the content after the start tag, when no more attributes can come.
+    /// With `recover` missing attributes are treated as if they were present.
+    fn close(&self, p: &Rc<Pattern>, recover: bool) -> Rc<Pattern> {
+        let close = |p| self.close(p, recover);
+        match &**p {
+            Pattern::Attribute(..) if recover => empty(),
+            Pattern::Attribute(..) => not_allowed(),
+            Pattern::Choice(a, b) => choice(close(a), close(b)),
+            Pattern::Interleave(a, b) => interleave(close(a), close(b)),
+            Pattern::Group(a, b) => group(close(a), close(b)),
+            Pattern::OneOrMore(a) => one_or_more(close(a)),
+            Pattern::Ref(index) => close(self.define(*index)),
+            _ => p.clone(),
+        }
+    }
+
+    fn nullable(&self, p: &Pattern) -> bool {
+        match p {
+            Pattern::Empty | Pattern::Text => true,
+            Pattern::Choice(a, b) => self.nullable(a) || self.nullable(b),
+            Pattern::Interleave(a, b) | Pattern::Group(a, b) => {
+                self.nullable(a) && self.nullable(b)
+            }
+            Pattern::OneOrMore(a) => self.nullable(a),
+            Pattern::Ref(index) => self.nullable(self.define(*index)),
+            _ => false,
+        }
+    }
+
+    /// the names of the attributes that are missing if the start tag ends now
+    fn required_attributes(&self, p: &Rc<Pattern>, missing: &mut Vec<String>) {
+        match &**p {
+            Pattern::Attribute(name, _) => missing.push(name.describe()),
+            // an attribute is only required if it is in both alternatives
+            Pattern::Choice(a, b)
+                if is_not_allowed(&self.close(a, false))
+                    && is_not_allowed(&self.close(b, false)) =>
+            {
+                self.required_attributes(a, missing);
+            }
+            Pattern::Interleave(a, b) | Pattern::Group(a, b) => {
+                self.required_attributes(a, missing);
+                self.required_attributes(b, missing);
+            }
+            Pattern::OneOrMore(a) => self.required_attributes(a, missing),
+            Pattern::Ref(index) => self.required_attributes(self.define(*index), missing),
+            _ => {}
+        }
+    }
+
+    /// the elements or text that are missing if the content ends now
+    fn required_content(
+        &self,
+        p: &Pattern,
+        missing: &mut Vec<String>,
+        visited: &mut HashSet<usize>,
+    ) {
+        let mut add = |item: String| {
+            if !missing.contains(&item) {
+                missing.push(item);
+            }
+        };
+        match p {
+            Pattern::Element(name, _) => add(format!("element {}", name.describe())),
+            Pattern::Data(_) | Pattern::Value(..) => add("text".into()),
+            Pattern::Choice(a, b) if !self.nullable(a) && !self.nullable(b) => {
+                self.required_content(a, missing, visited);
+            }
+            Pattern::Interleave(a, b) | Pattern::Group(a, b) => {
+                if !self.nullable(a) {
+                    self.required_content(a, missing, visited);
+                }
+                if !self.nullable(b) {
+                    self.required_content(b, missing, visited);
+                }
+            }
+            Pattern::OneOrMore(a) => self.required_content(a, missing, visited),
+            Pattern::Ref(index) if visited.insert(*index) => {
+                self.required_content(self.define(*index), missing, visited);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Marks the element as invalid and reports the violation if violations are collected.
+/// Returns false if the check can stop.
+fn record(
+    valid: &mut bool,
+    report: &mut Option<&mut Vec<Violation>>,
+    path: &str,
+    message: String,
+) -> bool {
+    *valid = false;
+    match report {
+        Some(violations) => {
+            violations.push(Violation::new(path, message));
+            true
+        }
+        None => false,
+    }
+}
+
+fn grammar_error(reason: impl Into<String>) -> PetriError {
+    PetriError::invalid(None, format!("grammar: {}", reason.into()))
+}
+
+fn file<'a>(grammars: &'a HashMap<&str, XmlElement>, href: &str) -> Result<&'a XmlElement> {
+    let name = href.rsplit('/').next().unwrap_or(href);
+    match grammars.get(name) {
+        Some(grammar) if grammar.namespace == RNG_NS && grammar.name == "grammar" => Ok(grammar),
+        Some(_) => Err(grammar_error(format!("{} is no grammar", name))),
+        None => Err(grammar_error(format!("{} is unknown", name))),
+    }
+}
+
+/// the starts and defines of the grammar, including the included grammars
+fn collect_components<'a>(
+    grammars: &'a HashMap<&str, XmlElement>,
+    grammar: &'a XmlElement,
+    context: Context<'a>,
+    components: &mut Vec<Component<'a>>,
+) -> Result<()> {
+    for child in grammar.rng_children() {
+        let context = context.enter(child);
+        match child.name.as_str() {
+            "start" | "define" => {
+                let name = match child.name.as_str() {
+                    "define" => Some(
+                        child
+                            .attribute("name")
+                            .ok_or_else(|| grammar_error("define without name"))?,
+                    ),
+                    _ => None,
+                };
+                components.push(Component {
+                    name,
+                    combine: child.attribute("combine"),
+                    body: child,
+                    context,
+                });
+            }
+            "div" => collect_components(grammars, child, context, components)?,
+            "include" => {
+                let href = child
+                    .attribute("href")
+                    .ok_or_else(|| grammar_error("include without href"))?;
+                let included = file(grammars, href)?;
+                let mut overrides = Vec::new();
+                collect_components(grammars, child, context, &mut overrides)?;
+                let mut inner = Vec::new();
+                collect_components(grammars, included, context.enter(included), &mut inner)?;
+                inner.retain(|component| !overrides.iter().any(|o| o.name == component.name));
+                components.extend(inner);
+                components.extend(overrides);
+            }
+            other => {
+                return Err(grammar_error(format!(
+                    "unsupported element {} in grammar",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Compiler<'a> {
+    index: &'a HashMap<&'a str, usize>,
+}
+
+impl<'a> Compiler<'a> {
+    /// the children of the element as group
+    fn group(&self, element: &XmlElement, context: Context) -> Result<Rc<Pattern>> {
+        self.sequence(element.rng_children(), context)
+    }
+
+    fn sequence<'e>(
+        &self,
+        children: impl Iterator<Item = &'e XmlElement>,
+        context: Context,
+    ) -> Result<Rc<Pattern>> {
+        let mut pattern = empty();
+        for child in children {
+            pattern = group(pattern, self.pattern(child, context)?);
+        }
+        Ok(pattern)
+    }
+
+    fn combined(
+        &self,
+        element: &XmlElement,
+        context: Context,
+        combine: fn(Rc<Pattern>, Rc<Pattern>) -> Rc<Pattern>,
+    ) -> Result<Rc<Pattern>> {
+        let mut pattern: Option<Rc<Pattern>> = None;
+        for child in element.rng_children() {
+            let child = self.pattern(child, context)?;
+            pattern = Some(match pattern {
+                Some(before) => combine(before, child),
+                None => child,
+            });
+        }
+        pattern.ok_or_else(|| grammar_error(format!("empty {}", element.name)))
+    }
+
+    fn pattern(&self, element: &XmlElement, context: Context) -> Result<Rc<Pattern>> {
+        let context = context.enter(element);
+        Ok(match element.name.as_str() {
+            "element" | "attribute" => {
+                let is_element = element.name == "element";
+                let mut children = element.rng_children();
+                let name_class = match element.attribute("name") {
+                    Some(name) => {
+                        // attributes are only in a namespace if it is given explicitly
+                        let ns = match is_element {
+                            true => context.ns,
+                            false => element.attribute("ns").unwrap_or(""),
+                        };
+                        NameClass::Name(ns.into(), name.into())
+                    }
+                    None => {
+                        let first = children
+                            .next()
+                            .ok_or_else(|| grammar_error("missing name class"))?;
+                        self.name_class(first, context)?
+                    }
+                };
+                let content = self.sequence(children, context)?;
+                if is_element {
+                    Rc::new(Pattern::Element(Rc::new(name_class), content))
+                } else {
+                    let content = match &*content {
+                        Pattern::Empty => Rc::new(Pattern::Text),
+                        _ => content,
+                    };
+                    Rc::new(Pattern::Attribute(Rc::new(name_class), content))
+                }
+            }
+            "group" => self.group(element, context)?,
+            "interleave" => self.combined(element, context, interleave)?,
+            "choice" => self.combined(element, context, choice)?,
+            "optional" => choice(self.group(element, context)?, empty()),
+            "zeroOrMore" => choice(one_or_more(self.group(element, context)?), empty()),
+            "oneOrMore" => one_or_more(self.group(element, context)?),
+            "mixed" => interleave(self.group(element, context)?, Rc::new(Pattern::Text)),
+            "ref" => {
+                let name = element.attribute("name").unwrap_or("");
+                match self.index.get(name) {
+                    Some(index) => Rc::new(Pattern::Ref(*index)),
+                    None => return Err(grammar_error(format!("reference to unknown {}", name))),
+                }
+            }
+            "empty" => empty(),
+            "text" => Rc::new(Pattern::Text),
+            "notAllowed" => not_allowed(),
+            "data" => {
+                let name = element.attribute("type").unwrap_or("");
+                let mut datatype = Datatype::new(context.library, name)?;
+                for param in element.rng_children() {
+                    let limit = param.text().trim().parse::<f64>();
+                    let facet = match (param.name.as_str(), param.attribute("name"), limit) {
+                        ("param", Some("minInclusive"), Ok(limit)) => Facet::MinInclusive(limit),
+                        ("param", Some("minExclusive"), Ok(limit)) => Facet::MinExclusive(limit),
+                        ("param", Some("maxInclusive"), Ok(limit)) => Facet::MaxInclusive(limit),
+                        ("param", Some("maxExclusive"), Ok(limit)) => Facet::MaxExclusive(limit),
+                        _ => {
+                            return Err(grammar_error(format!(
+                                "unsupported {} of data",
+                                param.name
+                            )))
+                        }
+                    };
+                    datatype.facets.push(facet);
+                }
+                Rc::new(Pattern::Data(Rc::new(datatype)))
+            }
+            "value" => {
+                let datatype = match element.attribute("type") {
+                    Some(name) => Datatype::new(context.library, name)?,
+                    None => Datatype::new("", "token")?,
+                };
+                Rc::new(Pattern::Value(Rc::new(datatype), element.text()))
+            }
+            other => return Err(grammar_error(format!("unsupported pattern {}", other))),
+        })
+    }
+
+    fn name_class(&self, element: &XmlElement, context: Context) -> Result<NameClass> {
+        let context = context.enter(element);
+        let except = |element: &XmlElement| -> Result<Option<Box<NameClass>>> {
+            match element.rng_children().next() {
+                Some(except) if except.name == "except" => {
+                    let mut classes = except.rng_children();
+                    let first = classes
+                        .next()
+                        .ok_or_else(|| grammar_error("empty except"))?;
+                    let mut class = self.name_class(first, context)?;
+                    for other in classes {
+                        class = NameClass::Choice(
+                            Box::new(class),
+                            Box::new(self.name_class(other, context)?),
+                        );
+                    }
+                    Ok(Some(Box::new(class)))
+                }
+                Some(_) => Err(grammar_error("invalid name class")),
+                None => Ok(None),
+            }
+        };
+        Ok(match element.name.as_str() {
+            "name" => NameClass::Name(context.ns.into(), element.text().trim().into()),
+            "anyName" => NameClass::AnyName(except(element)?),
+            "nsName" => NameClass::NsName(context.ns.into(), except(element)?),
+            "choice" => {
+                let mut classes = element.rng_children();
+                let first = classes
+                    .next()
+                    .ok_or_else(|| grammar_error("empty choice of names"))?;
+                let mut class = self.name_class(first, context)?;
+                for other in classes {
+                    class = NameClass::Choice(
+                        Box::new(class),
+                        Box::new(self.name_class(other, context)?),
+                    );
+                }
+                class
+            }
+            other => return Err(grammar_error(format!("unsupported name class {}", other))),
+        })
+    }
+}
@@ -0,0 +1,116 @@
+//! Behaviour of place/transition nets.
+//!
+//! A [`Marking`] tells the number of tokens on every place. A transition is enabled if
+//! every place of its preset holds at least as many tokens as the inscription of the arc
+//! between them, firing it consumes these tokens and produces tokens on the places of its
+//! postset. Pages and reference nodes have no influence on the behaviour, the net is
+//! looked at as if it was flattened.
+
+use crate::compiled::CompiledNet;
+use crate::*;
+use std::ops::Index;
+
+/// The number of tokens on each place of a net.
+/// The places are indexed in the order of [`PetriNet::places`].
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone)]
+pub struct Marking(Vec<usize>);
+
+impl Marking {
+    pub fn new(tokens: Vec<usize>) -> Self {
+        Marking(tokens)
+    }
+
+    /// the tokens of all places
+    pub fn tokens(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl From<Vec<usize>> for Marking {
+    fn from(tokens: Vec<usize>) -> Self {
+        Marking(tokens)
+    }
+}
+
+impl Index<usize> for Marking {
+    type Output = usize;
+
+    fn index(&self, place: usize) -> &usize {
+        &self.0[place]
+    }
+}
+
+/// The index of the transition (or reference transition) in the compiled net.
+/// Fails if the marking does not fit the net.
+fn prepare(net: &CompiledNet, transition: &NodeRef, marking: &Marking) -> Result<usize> {
+    let index = net
+        .transition_index(transition)
+        .ok_or_else(|| transition.not_found())?;
+    if marking.0.len() != net.place_count() {
+        return Err(PetriError::invalid(
+            None,
+            format!(
+                "the marking has {} places, the net {}",
+                marking.0.len(),
+                net.place_count()
+            ),
+        ));
+    }
+    Ok(index)
+}
+
+impl CompiledNet {
+    /// Tells if the transition (or reference transition) of the compiled net can fire in
+    /// the marking, like [`PetriNet::is_enabled`] but without compiling the net again
+    pub fn is_node_enabled(&self, transition: &NodeRef, marking: &Marking) -> Result<bool> {
+        let index =
+            prepare(self, transition, marking).map_err(|e| e.context(Operation::Fire, None))?;
+        Ok(self.is_enabled(index, marking))
+    }
+
+    /// The marking reached by firing the transition (or reference transition) of the
+    /// compiled net, like [`PetriNet::fire`] but without compiling the net again.
+    /// Fails if the transition is not enabled.
+    pub fn fire_node(&self, transition: &NodeRef, marking: &Marking) -> Result<Marking> {
+        let fire = |e: PetriError| e.context(Operation::Fire, None);
+        let index = prepare(self, transition, marking).map_err(fire)?;
+        let id = PNMLID::new(self.transition_id(index));
+        if !self.is_enabled(index, marking) {
+            return Err(fire(PetriError::invalid(
+                Some(&id),
+                "the transition is not enabled",
+            )));
+        }
+        self.fire(index, marking)
+            .ok_or_else(|| fire(PetriError::invalid(Some(&id), "too many tokens")))
+    }
+}
+
+impl PetriNet {
+    /// The marking given by the initial markings of the places, 0 tokens where none is set
+    pub fn initial_marking(&self) -> Result<Marking> {
+        self.places()
+            .map(|place| place.get_initial_marking(self))
+            .collect::<Result<Vec<usize>>>()
+            .map(Marking)
+    }
+
+    /// Tells if the transition (or reference transition) can fire in the marking.
+    /// The net is compiled on every call, compile it once and use
+    /// [`CompiledNet::is_node_enabled`] to check many transitions.
+    pub fn is_enabled(&self, transition: &NodeRef, marking: &Marking) -> Result<bool> {
+        self.compile()
+            .map_err(|e| e.context(Operation::Fire, None))?
+            .is_node_enabled(transition, marking)
+    }
+
+    /// The marking reached by firing the transition (or reference transition) in the marking.
+    /// Fails if the transition is not enabled.
+    /// The net is compiled on every call, compile it once and use
+    /// [`CompiledNet::fire_node`] to fire many transitions.
+    pub fn fire(&self, transition: &NodeRef, marking: &Marking) -> Result<Marking> {
+        self.compile()
+            .map_err(|e| e.context(Operation::Fire, None))?
+            .fire_node(transition, marking)
+    }
+}
@@ -0,0 +1,191 @@
+//! Random simulation of place/transition nets (the token game).
+//!
+//! A simulation starts at the initial marking and fires one enabled transition after the
+//! other, chosen by a [`Policy`], until no transition is enabled, the step bound is reached
+//! or a predicate on the marking holds. Unlike the exploration of all reachable markings it
+//! needs memory only for the current marking and the trace, so it can smoke-test nets that
+//! are far too large to explore.
+
+use crate::compiled::CompiledNet;
+use crate::deadlock::TraceStep;
+use crate::semantics::Marking;
+use crate::*;
+
+/// Chooses the transition to fire in each step of a simulation
+pub trait Policy {
+    /// Called once before the first step of a simulation of the net, the same policy may
+    /// be used for several nets
+    fn prepare(&mut self, _net: &CompiledNet) {}
+
+    /// Returns one of the enabled transitions, by their index in the [`CompiledNet`].
+    /// `enabled` is ordered by index and never empty.
+    fn choose(&mut self, net: &CompiledNet, marking: &Marking, enabled: &[usize]) -> usize;
+}
+
+/// Chooses uniformly at random. The same seed gives the same choices on the same net.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    /// the next number of the SplitMix64 generator
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl Policy for Random {
+    fn choose(&mut self, _: &CompiledNet, _: &Marking, enabled: &[usize]) -> usize {
+        // scales the number to the range without the bias of a remainder
+        let choice = (u128::from(self.next()) * enabled.len() as u128) >> 64;
+        enabled[choice as usize]
+    }
+}
+
+/// Chooses the enabled transition that comes first in a list of transition ids.
+/// Transitions that are not listed come after all listed ones, ordered by index.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Priority {
+    ids: Vec<String>,
+    /// the rank of every transition of the net being simulated
+    ranks: Vec<usize>,
+}
+
+impl Priority {
+    pub fn new(ids: &[&str]) -> Self {
+        Priority {
+            ids: ids.iter().map(|id| String::from(*id)).collect(),
+            ranks: Vec::new(),
+        }
+    }
+}
+
+impl Policy for Priority {
+    fn prepare(&mut self, net: &CompiledNet) {
+        self.ranks = (0..net.transition_count())
+            .map(|t| self.ids.len() + t)
+            .collect();
+        for (rank, id) in self.ids.iter().enumerate().rev() {
+            if let Some(t) = net.transition_index_of_id(id) {
+                self.ranks[t] = rank;
+            }
+        }
+    }
+
+    fn choose(&mut self, _: &CompiledNet, _: &Marking, enabled: &[usize]) -> usize {
+        // transitions without rank come last, if the policy was not prepared for the net
+        enabled
+            .iter()
+            .copied()
+            .min_by_key(|t| self.ranks.get(*t).copied().unwrap_or(usize::MAX))
+            .unwrap_or_default()
+    }
+}
+
+/// A policy given by a function of the net, the marking and the enabled transitions
+pub struct Callback<F> {
+    function: F,
+}
+
+impl<F> Callback<F>
+where
+    F: FnMut(&CompiledNet, &Marking, &[usize]) -> usize,
+{
+    pub fn new(function: F) -> Self {
+        Callback { function }
+    }
+}
+
+impl<F> Policy for Callback<F>
+where
+    F: FnMut(&CompiledNet, &Marking, &[usize]) -> usize,
+{
+    fn choose(&mut self, net: &CompiledNet, marking: &Marking, enabled: &[usize]) -> usize {
+        (self.function)(net, marking, enabled)
+    }
+}
+
+/// Why a simulation ended
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum StopReason {
+    /// no transition is enabled in the last marking
+    Deadlock,
+    /// the maximal number of steps was fired
+    StepBound,
+    /// the predicate holds in the last marking
+    Predicate,
+    /// the chosen transition would put more tokens on a place than can be counted
+    TooManyTokens,
+}
+
+/// The transitions fired in a simulation and the marking it ended in
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Simulation {
+    pub trace: Vec<TraceStep>,
+    pub marking: Marking,
+    pub stop: StopReason,
+}
+
+impl PetriNet {
+    /// Fires at most `max_steps` transitions chosen by the policy, starting at the initial
+    /// marking. Stops early in a dead marking or in a marking where `until` holds, which is
+    /// checked for the initial marking as well.
+    /// Fails if the policy chooses a transition that is not enabled.
+    pub fn simulate(
+        &self,
+        policy: &mut impl Policy,
+        max_steps: usize,
+        mut until: impl FnMut(&Marking) -> bool,
+    ) -> Result<Simulation> {
+        let net = self.compile()?;
+        policy.prepare(&net);
+        let mut marking = net.initial_marking().clone();
+        let mut trace = Vec::new();
+        let stop = loop {
+            if until(&marking) {
+                break StopReason::Predicate;
+            }
+            if trace.len() >= max_steps {
+                break StopReason::StepBound;
+            }
+            let enabled: Vec<usize> = net.enabled(&marking).collect();
+            if enabled.is_empty() {
+                break StopReason::Deadlock;
+            }
+            let index = policy.choose(&net, &marking, &enabled);
+            if !enabled.contains(&index) {
+                let id =
+                    (index < net.transition_count()).then(|| PNMLID::new(net.transition_id(index)));
+                return Err(PetriError::invalid(
+                    id.as_ref(),
+                    "the policy chose a transition that is not enabled",
+                )
+                .context(Operation::Fire, None));
+            }
+            marking = match net.fire(index, &marking) {
+                Some(next) => next,
+                None => break StopReason::TooManyTokens,
+            };
+            let transition = net.transition(index);
+            trace.push(TraceStep {
+                transition,
+                id: net.transition_id(index).into(),
+                name: transition.get_name(self)?.map(String::from),
+            });
+        };
+        Ok(Simulation {
+            trace,
+            marking,
+            stop,
+        })
+    }
+}
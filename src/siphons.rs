@@ -0,0 +1,206 @@
+//! Siphons and traps of a place/transition net.
+//!
+//! A siphon is a set of places where every transition producing tokens on the set also
+//! consumes tokens from it: once a siphon is empty it stays empty, so the transitions
+//! after it are dead. A trap is a set of places where every transition consuming from the
+//! set also produces on it: once a trap is marked it stays marked. A siphon that contains
+//! a marked trap can therefore never be emptied.
+//!
+//! By Commoner's theorem an ordinary free choice net is live if and only if every siphon
+//! contains a trap marked in the initial marking. It is enough to check the minimal siphons.
+
+use crate::compiled::CompiledNet;
+use crate::*;
+
+/// A set of places with their handles and ids, ordered by index
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PlaceSet {
+    indices: Vec<usize>,
+    nodes: Vec<NodeRef>,
+    ids: Vec<String>,
+}
+
+impl PlaceSet {
+    fn new(net: &CompiledNet, places: &[bool]) -> Self {
+        let indices: Vec<usize> = (0..places.len()).filter(|p| places[*p]).collect();
+        PlaceSet {
+            nodes: indices.iter().map(|p| net.place(*p)).collect(),
+            ids: indices.iter().map(|p| net.place_id(*p).into()).collect(),
+            indices,
+        }
+    }
+
+    /// the indices of the places in the [`CompiledNet`]
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    pub fn nodes(&self) -> &[NodeRef] {
+        &self.nodes
+    }
+
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    pub fn contains(&self, place: &NodeRef) -> bool {
+        self.nodes.contains(place)
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// A minimal siphon with the largest trap inside of it
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Siphon {
+    pub places: PlaceSet,
+    /// the union of all traps in the siphon, empty if it contains none
+    pub maximal_trap: PlaceSet,
+    /// true if a place of the trap is marked in the initial marking
+    pub trap_marked: bool,
+}
+
+/// The result of Commoner's check, see the [module documentation](self)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum CommonerVerdict {
+    /// every siphon contains an initially marked trap, the net is live
+    Live,
+    /// the siphon contains no initially marked trap and can be emptied, the net is not live
+    NotLive(Siphon),
+    /// the net has an arc weight above one or two transitions share only some of their
+    /// input places, the theorem does not apply
+    NotFreeChoice,
+}
+
+impl PetriNet {
+    /// The minimal siphons of the net, see the [module documentation](crate::siphons)
+    pub fn minimal_siphons(&self) -> Result<Vec<Siphon>> {
+        Ok(self.compile()?.minimal_siphons())
+    }
+
+    /// Checks the liveness of a free choice net with Commoner's theorem
+    pub fn commoner(&self) -> Result<CommonerVerdict> {
+        Ok(self.compile()?.commoner())
+    }
+}
+
+impl CompiledNet {
+    /// All minimal siphons, ordered by the indices of their places.
+    /// The number of minimal siphons can grow exponentially with the size of the net.
+    pub fn minimal_siphons(&self) -> Vec<Siphon> {
+        let mut siphons = Vec::new();
+        let none = vec![false; self.place_count()];
+        self.find_siphons(none.clone(), none, &mut siphons);
+        siphons.sort_unstable_by(|a, b| b.cmp(a));
+        let marking = self.initial_marking();
+        siphons
+            .into_iter()
+            .map(|siphon| {
+                let trap = self.maximal_trap(siphon.clone());
+                Siphon {
+                    places: PlaceSet::new(self, &siphon),
+                    trap_marked: (0..trap.len()).any(|p| trap[p] && marking[p] > 0),
+                    maximal_trap: PlaceSet::new(self, &trap),
+                }
+            })
+            .collect()
+    }
+
+    /// Checks the liveness of a free choice net with Commoner's theorem
+    pub fn commoner(&self) -> CommonerVerdict {
+        // the theorem holds for extended free choice nets as well
+        if self.ordinary_witness().is_some() || self.extended_free_choice_witness().is_some() {
+            return CommonerVerdict::NotFreeChoice;
+        }
+        match self
+            .minimal_siphons()
+            .into_iter()
+            .find(|siphon| !siphon.trap_marked)
+        {
+            Some(siphon) => CommonerVerdict::NotLive(siphon),
+            None => CommonerVerdict::Live,
+        }
+    }
+
+    /// Finds the minimal siphons that contain the required places and none of the excluded.
+    /// A siphon S that is minimal among those with the required places is searched first.
+    /// Every other minimal siphon misses one of the other places of S, so the search goes on
+    /// without the first of them, then with the first but without the second and so on.
+    fn find_siphons(&self, excluded: Vec<bool>, required: Vec<bool>, siphons: &mut Vec<Vec<bool>>) {
+        let contains_required =
+            |places: &[bool]| (0..places.len()).all(|p| !required[p] || places[p]);
+        let mut siphon = self.maximal_siphon(excluded.iter().map(|e| !e).collect());
+        if !siphon.contains(&true) || !contains_required(&siphon) {
+            return;
+        }
+        // a place that cannot be left out stays needed when the siphon shrinks further
+        for place in 0..siphon.len() {
+            if siphon[place] && !required[place] {
+                let mut smaller = siphon.clone();
+                smaller[place] = false;
+                let smaller = self.maximal_siphon(smaller);
+                if smaller.contains(&true) && contains_required(&smaller) {
+                    siphon = smaller;
+                }
+            }
+        }
+        let is_minimal = (0..siphon.len()).filter(|p| siphon[*p]).all(|place| {
+            let mut smaller = siphon.clone();
+            smaller[place] = false;
+            !self.maximal_siphon(smaller).contains(&true)
+        });
+        let mut excluded = excluded;
+        let mut required = required;
+        for place in 0..siphon.len() {
+            if siphon[place] && !required[place] {
+                excluded[place] = true;
+                self.find_siphons(excluded.clone(), required.clone(), siphons);
+                excluded[place] = false;
+                required[place] = true;
+            }
+        }
+        if is_minimal {
+            siphons.push(siphon);
+        }
+    }
+
+    /// The largest siphon in the places: removes places with an input transition
+    /// that consumes from none of the remaining places
+    fn maximal_siphon(&self, mut places: Vec<bool>) -> Vec<bool> {
+        loop {
+            let removable = (0..places.len()).find(|p| {
+                places[*p]
+                    && self
+                        .place_pre(*p)
+                        .any(|(t, _)| !self.pre(t).any(|(input, _)| places[input]))
+            });
+            match removable {
+                Some(place) => places[place] = false,
+                None => return places,
+            }
+        }
+    }
+
+    /// The largest trap in the places: removes places with an output transition
+    /// that produces on none of the remaining places
+    fn maximal_trap(&self, mut places: Vec<bool>) -> Vec<bool> {
+        loop {
+            let removable = (0..places.len()).find(|p| {
+                places[*p]
+                    && self
+                        .place_post(*p)
+                        .any(|(t, _)| !self.post(t).any(|(output, _)| places[output]))
+            });
+            match removable {
+                Some(place) => places[place] = false,
+                None => return places,
+            }
+        }
+    }
+}
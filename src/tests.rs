@@ -19,3 +19,1951 @@ fn serialize() {
     place3.initial_marking(net1, 7).unwrap();
     println!("{}", doc.to_xml().unwrap());
 }
+
+#[test]
+fn nested_sub_pages() {
+    use crate::*;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let top = net.add_page(None);
+    let first = net.add_sub_page(None, &top).unwrap();
+    let second = net.add_sub_page(None, &top).unwrap();
+    // a page two levels down belongs to its parent, not to the last page added
+    let nested = net.add_sub_page(None, &first).unwrap();
+    let place = net.add_place(&nested).unwrap();
+    assert_eq!(
+        top.sub_pages(net).unwrap().collect::<Vec<_>>(),
+        vec![first, second]
+    );
+    assert_eq!(
+        first.sub_pages(net).unwrap().collect::<Vec<_>>(),
+        vec![nested]
+    );
+    assert_eq!(second.sub_pages(net).unwrap().count(), 0);
+    let (first, second, nested, place) = (
+        first.id(net).unwrap().to_string(),
+        second.id(net).unwrap().to_string(),
+        nested.id(net).unwrap().to_string(),
+        place.id(net).unwrap().to_string(),
+    );
+    let xml = doc.to_xml().unwrap();
+    let position = |id: &str| xml.find(&format!("id=\"{}\"", id)).unwrap();
+    assert!(position(&first) < position(&nested));
+    assert!(position(&nested) < position(&place));
+    assert!(position(&place) < position(&second));
+}
+
+#[test]
+fn deserialize() {
+    use crate::*;
+    let doc = PNMLDocument::from_reader(
+        std::fs::File::open("pnml/Example_philosophers.xml").expect("example file is missing"),
+    )
+    .unwrap();
+    assert_eq!(doc.petri_nets().len(), 1);
+    let xml = doc.to_xml().unwrap();
+    assert_eq!(xml.matches("<place ").count(), 30);
+    assert_eq!(xml.matches("<transition ").count(), 30);
+    assert_eq!(xml.matches("<arc ").count(), 96);
+    assert_eq!(xml.matches("<initialMarking>").count(), 12);
+    assert!(xml.contains("<text>FORK_1</text>"));
+    assert!(xml.contains("<position x=\"500\" y=\"692\" />"));
+    assert_eq!(xml.matches("<offset ").count(), 72);
+    // the written document can be read again
+    let reread = PNMLDocument::from_xml(&xml).unwrap();
+    assert_eq!(reread.to_xml().unwrap(), xml);
+}
+
+#[test]
+fn deserialize_references() {
+    use crate::*;
+    let xml = r#"<?xml version="1.0"?>
+<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p">
+      <referencePlace id="r" ref="p1"/>
+      <page id="sub">
+        <place id="p1"><initialMarking><text> 3 </text></initialMarking></place>
+      </page>
+    </page>
+  </net>
+</pnml>"#;
+    let doc = PNMLDocument::from_xml(xml).unwrap();
+    let written = doc.to_xml().unwrap();
+    assert!(written.contains("<referencePlace ref=\"p1\" id=\"r\""));
+    assert!(written.contains("<text>3</text>"));
+    assert!(PNMLDocument::from_xml(&xml.replace("ref=\"p1\"", "ref=\"p2\"")).is_err());
+}
+
+#[test]
+fn errors() {
+    use crate::*;
+    use std::error::Error;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let place1 = net.add_place(&page).unwrap();
+    let place2 = net.add_place(&page).unwrap();
+    let error = net.add_arc(&page, &place1, &place2).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::BipartitionViolation);
+    assert_eq!(
+        error.to_string(),
+        "adding arc n0_p0_o2: arcs cannot connect two nodes of the same type (n0_p0_o0 -> n0_p0_o1)"
+    );
+    assert!(error.source().is_some());
+    // a node that is gone is reported with the page the arc was added on
+    let transition = net.add_transition(&page).unwrap();
+    net.remove_node(&place2).unwrap();
+    let error = net.add_arc(&page, &place2, &transition).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::PlaceNotFound);
+    assert!(error.to_string().ends_with(" not found on page n0_p0"));
+    match error.root() {
+        PetriError::PlaceNotFound { page, .. } => assert_eq!(page.as_deref(), Some("n0_p0")),
+        error => panic!("unexpected error {}", error),
+    }
+    // a handle of the wrong kind finds the page of the object it points to
+    let wrong = match place1 {
+        NodeRef::PlaceRef(key) => NodeRef::TransitionRef(key),
+        NodeRef::TransitionRef(key) => NodeRef::PlaceRef(key),
+    };
+    let error = wrong.get_name(net).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TransitionNotFound);
+    assert!(error.to_string().ends_with(" not found on page n0_p0"));
+
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p">
+      <referencePlace id="r" ref="missing"/>
+    </page>
+  </net>
+</pnml>"#;
+    let error = PNMLDocument::from_xml(xml).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::PlaceNotFound);
+    assert_eq!(
+        error.to_string(),
+        "reading net n: resolving reference node r: place missing not found"
+    );
+    let error = PNMLDocument::from_xml("<pnml><net id=\"n\"/></pnml>").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    let error = PNMLDocument::from_xml("<pnml><net").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::XmlReaderError);
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn graphics() {
+    use crate::graphics::*;
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let mut place = net.add_place(&page).unwrap();
+    let transition = net.add_transition(&page).unwrap();
+    let mut arc = net.add_arc(&page, &place, &transition).unwrap();
+    place
+        .name(net, "p")
+        .unwrap()
+        .name_offset(net, 0.0, -10.0)
+        .unwrap()
+        .position(net, 100.0, 50.5)
+        .unwrap()
+        .fill(
+            net,
+            Fill {
+                color: Some("#ff0000".into()),
+                ..Fill::default()
+            },
+        )
+        .unwrap()
+        .initial_marking(net, 2)
+        .unwrap()
+        .initial_marking_offset(net, 5.0, 5.0)
+        .unwrap();
+    arc.add_waypoint(net, 1.0, 2.0)
+        .unwrap()
+        .line(
+            net,
+            Line {
+                style: Some(LineStyle::Dash),
+                ..Line::default()
+            },
+        )
+        .unwrap()
+        .inscription(net, NonZeroUsize::new(3).unwrap())
+        .unwrap()
+        .inscription_offset(net, 1.0, 1.0)
+        .unwrap();
+    assert!(arc.name_offset(net, 1.0, 1.0).is_err());
+    assert_eq!(
+        place.get_graphics(net).unwrap().unwrap().position,
+        Coordinate { x: 100.0, y: 50.5 }
+    );
+    let xml = doc.to_xml().unwrap();
+    assert!(xml.contains("<position x=\"100\" y=\"50.5\" />"));
+    assert!(xml.contains("<fill color=\"#ff0000\" />"));
+    assert!(xml.contains("<line style=\"dash\" />"));
+    assert!(xml.contains("<offset x=\"5\" y=\"5\" />"));
+    // changing the values keeps the offsets
+    place
+        .initial_marking(doc.petri_net_data(net_ref).unwrap(), 4)
+        .unwrap();
+    arc.inscription(
+        doc.petri_net_data(net_ref).unwrap(),
+        NonZeroUsize::new(2).unwrap(),
+    )
+    .unwrap();
+    let xml = doc.to_xml().unwrap();
+    assert!(xml.contains("<text>4</text>"));
+    assert!(xml.contains("<offset x=\"5\" y=\"5\" />"));
+    assert!(xml.contains("<offset x=\"1\" y=\"1\" />"));
+    let reread = PNMLDocument::from_xml(&xml).unwrap();
+    assert_eq!(reread.to_xml().unwrap(), xml);
+    assert_eq!(doc.check_structure().unwrap(), vec![]);
+}
+
+#[test]
+fn tool_specific() {
+    use crate::tool_specific::*;
+    use crate::*;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <toolspecific tool="editor" version="1.0"><layout grid="10"><snap/></layout>text</toolspecific>
+    <page id="p">
+      <place id="p1">
+        <name><text>a</text><toolspecific tool="editor" version="1.0"><bold/></toolspecific></name>
+        <toolspecific tool="other" version="2"><x/></toolspecific>
+      </place>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let written = doc.to_xml().unwrap();
+    assert!(written.contains("<snap />"));
+    assert!(written.contains("<bold />"));
+    assert_eq!(
+        PNMLDocument::from_xml(&written).unwrap().to_xml().unwrap(),
+        written
+    );
+
+    let net_ref = doc.petri_nets()[0];
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let infos = net.get_tool_specific("editor");
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].version(), "1.0");
+    assert_eq!(
+        infos[0].content().unwrap(),
+        "<layout xmlns=\"http://www.pnml.org/version-2009/grammar/pnml\" grid=\"10\"><snap /></layout>text"
+    );
+    assert!(net.get_tool_specific("other").is_empty());
+
+    let page = net.add_page(None);
+    let mut place = net.add_place(&page).unwrap();
+    let info = ToolInfo::new("mine", "0.1", "<color>red</color>").unwrap();
+    place.add_tool_specific(net, info.clone()).unwrap();
+    assert!(place
+        .add_label_tool_specific(net, LabelKind::InitialMarking, info.clone())
+        .is_err());
+    place
+        .initial_marking(net, 1)
+        .unwrap()
+        .add_label_tool_specific(net, LabelKind::InitialMarking, info)
+        .unwrap();
+    assert_eq!(place.get_tool_specific(net, "mine").unwrap().len(), 1);
+    assert_eq!(
+        place
+            .get_label_tool_specific(net, LabelKind::InitialMarking, "mine")
+            .unwrap()[0]
+            .content()
+            .unwrap(),
+        "<color>red</color>"
+    );
+    place.initial_marking(net, 2).unwrap();
+    assert_eq!(
+        place
+            .get_label_tool_specific(net, LabelKind::InitialMarking, "mine")
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(ToolInfo::new("mine", "0.1", "<open>").is_err());
+}
+
+#[test]
+fn labels() {
+    use crate::*;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <declaration><text>const N = 3</text></declaration>
+    <page id="p">
+      <comment><text>first</text></comment>
+      <comment><text>second</text><graphics><offset x="1" y="2"/></graphics></comment>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let written = doc.to_xml().unwrap();
+    assert_eq!(
+        PNMLDocument::from_xml(&written).unwrap().to_xml().unwrap(),
+        written
+    );
+
+    let net_ref = doc.petri_nets()[0];
+    let net = doc.petri_net_data(net_ref).unwrap();
+    assert_eq!(
+        net.get_labels("declaration").unwrap(),
+        vec!["<text xmlns=\"http://www.pnml.org/version-2009/grammar/pnml\">const N = 3</text>"]
+    );
+    let mut page = net.pages()[0];
+    assert_eq!(page.get_labels(net, "comment").unwrap().len(), 2);
+    assert!(page.get_labels(net, "declaration").unwrap().is_empty());
+
+    net.add_label("comment", "<text>net comment</text>")
+        .unwrap();
+    page.add_label(net, "note", "<text>page note</text>")
+        .unwrap();
+    assert!(net.add_label("page", "").is_err());
+    assert!(page.add_label(net, "no label", "").is_err());
+    assert!(page.add_label(net, "note", "<open>").is_err());
+    assert_eq!(
+        page.get_labels(net, "note").unwrap(),
+        vec!["<text>page note</text>"]
+    );
+    let written = doc.to_xml().unwrap();
+    assert!(written.contains("<text>net comment</text>"));
+    assert!(written.contains("<text>page note</text>"));
+}
+
+#[test]
+fn traversal() {
+    use crate::*;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="top">
+      <place id="p"/>
+      <transition id="t"/>
+      <arc id="a1" source="p" target="t"/>
+      <page id="sub">
+        <referencePlace id="rp" ref="p"/>
+        <transition id="u"/>
+        <arc id="a2" source="u" target="rp"/>
+        <arc id="a3" source="rp" target="u"/>
+      </page>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let places: Vec<_> = net.places().collect();
+    assert_eq!(places.len(), 1);
+    assert_eq!(places[0].get_name(net).unwrap(), None);
+    assert_eq!(net.transitions().count(), 2);
+    assert_eq!(net.arcs().count(), 3);
+
+    let top = net.pages()[0];
+    let sub_pages: Vec<_> = top.sub_pages(net).unwrap().collect();
+    assert_eq!(sub_pages.len(), 1);
+    assert_eq!(sub_pages[0].sub_pages(net).unwrap().count(), 0);
+
+    let place = &places[0];
+    let preset: Vec<_> = place.preset(net).unwrap().collect();
+    let postset: Vec<_> = place.postset(net).unwrap().collect();
+    assert_eq!(preset.len(), 1);
+    assert_eq!(postset.len(), 2);
+    assert!(postset.contains(&preset[0]));
+    assert_eq!(
+        preset[0].postset(net).unwrap().collect::<Vec<_>>(),
+        vec![*place]
+    );
+    // the arc ends at the reference place, not the place itself
+    let arc = net
+        .arcs()
+        .find(|arc| matches!(arc.sink(net), Ok(NodeRef::PlaceRef { .. })))
+        .unwrap();
+    assert!(arc.sink(net).unwrap() != *place);
+    assert!(matches!(arc.source(net), Ok(NodeRef::TransitionRef { .. })));
+
+    // handles returned by the api match the traversal
+    let page = net.add_page(None);
+    let transition = net.add_transition(&page).unwrap();
+    let new_arc = net.add_arc(&page, place, &transition).unwrap();
+    assert!(net.transitions().any(|t| t == transition));
+    assert!(net.arcs().any(|arc| arc == new_arc));
+}
+
+#[test]
+fn getters() {
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    assert_eq!(net_ref.id(&doc).unwrap(), "n0");
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let mut place = net.add_place(&page).unwrap();
+    let transition = net.add_transition(&page).unwrap();
+    let mut arc = net.add_arc(&page, &place, &transition).unwrap();
+    assert_eq!(page.id(net).unwrap(), "n0_p0");
+    assert_eq!(place.id(net).unwrap(), "n0_p0_o0");
+    assert_eq!(transition.id(net).unwrap(), "n0_p0_o1");
+    assert_eq!(arc.id(net).unwrap(), "n0_p0_o2");
+
+    assert_eq!(place.get_initial_marking(net).unwrap(), 0);
+    place.initial_marking(net, 3).unwrap();
+    assert_eq!(place.get_initial_marking(net).unwrap(), 3);
+    assert!(transition.get_initial_marking(net).is_err());
+
+    assert_eq!(arc.get_inscription(net).unwrap().get(), 1);
+    arc.inscription(net, NonZeroUsize::new(4).unwrap()).unwrap();
+    assert_eq!(arc.get_inscription(net).unwrap().get(), 4);
+}
+
+#[test]
+fn user_ids() {
+    use crate::*;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net_with_id("model", None).unwrap();
+    let other_ref = doc.add_petri_net(None);
+    assert_eq!(
+        doc.add_petri_net_with_id("model", None).unwrap_err().kind(),
+        ErrorKind::DuplicateId
+    );
+    // ids are unique in the whole document, not only in a net
+    let other = doc.petri_net_data(other_ref).unwrap();
+    other.add_page_with_id("shared", None).unwrap();
+    let net = doc.petri_net_data(net_ref).unwrap();
+    assert_eq!(
+        net.add_page_with_id("shared", None).unwrap_err().kind(),
+        ErrorKind::DuplicateId
+    );
+    let page = net.add_page_with_id("main", None).unwrap();
+    let sub_page = net.add_sub_page_with_id("detail", None, &page).unwrap();
+    let place = net.add_place_with_id(&page, "buffer").unwrap();
+    let transition = net.add_transition_with_id(&sub_page, "consume").unwrap();
+    let arc = net
+        .add_arc_with_id(&page, "a", &place, &transition)
+        .unwrap();
+    assert!(net.add_place_with_id(&page, "buffer").is_err());
+    assert!(net.add_place_with_id(&page, "no id").is_err());
+    assert!(net.add_arc_with_id(&page, "b", &place, &place).is_err());
+    // the failed arc did not take the id
+    net.add_place_with_id(&page, "b").unwrap();
+    // generated ids skip the ones taken by the user
+    net.add_place_with_id(&page, "main_o4").unwrap();
+    let generated = net.add_place(&page).unwrap();
+    assert_eq!(generated.id(net).unwrap(), "main_o5");
+
+    assert_eq!(doc.find_by_id("model"), Some(Handle::Net(net_ref)));
+    assert_eq!(doc.find_by_id("main"), Some(Handle::Page(page)));
+    assert_eq!(doc.find_by_id("detail"), Some(Handle::Page(sub_page)));
+    assert_eq!(doc.find_by_id("buffer"), Some(Handle::Node(place)));
+    assert_eq!(doc.find_by_id("consume"), Some(Handle::Node(transition)));
+    assert_eq!(doc.find_by_id("a"), Some(Handle::Arc(arc)));
+    assert!(matches!(doc.find_by_id("shared"), Some(Handle::Page(_))));
+    assert_eq!(doc.find_by_id("missing"), None);
+
+    // the ids survive writing and reading
+    let read = PNMLDocument::from_xml(&doc.to_xml().unwrap()).unwrap();
+    assert!(matches!(read.find_by_id("buffer"), Some(Handle::Node(_))));
+    assert!(matches!(read.find_by_id("a"), Some(Handle::Arc(_))));
+    let duplicate = r#"<pnml><net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+        <page id="p"><place id="x"/><transition id="x"/></page></net></pnml>"#;
+    assert_eq!(
+        PNMLDocument::from_xml(duplicate).unwrap_err().kind(),
+        ErrorKind::DuplicateId
+    );
+}
+
+#[test]
+fn remove_and_move() {
+    use crate::*;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p1">
+      <place id="p"/>
+      <transition id="t"/>
+      <transition id="t2"/>
+      <arc id="a1" source="p" target="t"/>
+      <arc id="a2" source="t2" target="p"/>
+    </page>
+    <page id="p2">
+      <referencePlace id="rp" ref="p"/>
+      <referencePlace id="rr" ref="rp"/>
+      <transition id="u"/>
+      <arc id="a3" source="rr" target="u"/>
+    </page>
+    <page id="p3">
+      <page id="p4">
+        <place id="q"/>
+      </page>
+      <arc id="a4" source="q" target="t"/>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let handle = |id| match doc.find_by_id(id) {
+        Some(Handle::Node(node)) => node,
+        _ => panic!("no node {}", id),
+    };
+    let (p, t, t2, u) = (handle("p"), handle("t"), handle("t2"), handle("u"));
+    let a1 = match doc.find_by_id("a1") {
+        Some(Handle::Arc(arc)) => arc,
+        _ => panic!("no arc a1"),
+    };
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let pages = net.pages();
+    let sub_page = pages[2].sub_pages(net).unwrap().next().unwrap();
+
+    // moving keeps the connections and the handle
+    let moved = net.move_node(&t2, &pages[1]).unwrap();
+    assert_eq!(moved, t2);
+    assert_eq!(moved.id(net).unwrap(), "t2");
+    assert!(p.preset(net).unwrap().any(|node| node == moved));
+    assert_eq!(net.arcs().count(), 4);
+
+    net.remove_arc(&a1).unwrap();
+    assert!(a1.get_name(net).is_err());
+    assert!(net.remove_arc(&a1).is_err());
+    assert_eq!(net.arcs().count(), 3);
+    // the id is free again
+    net.add_place_with_id(&pages[0], "a1").unwrap();
+
+    // the reference places and all connected arcs go with the place
+    net.remove_node(&p).unwrap();
+    assert!(p.get_name(net).is_err());
+    assert_eq!(net.arcs().count(), 1);
+    assert_eq!(net.places().count(), 2);
+    // unrelated handles are still valid
+    assert_eq!(t.id(net).unwrap(), "t");
+    assert_eq!(u.id(net).unwrap(), "u");
+    assert_eq!(moved.id(net).unwrap(), "t2");
+
+    net.remove_page(&pages[2]).unwrap();
+    assert!(sub_page.id(net).is_err());
+    assert_eq!(net.pages().len(), 2);
+    assert_eq!(net.places().count(), 1);
+    assert_eq!(net.arcs().count(), 0);
+
+    let written = doc.to_xml().unwrap();
+    for id in ["p", "rp", "rr", "a2", "a3", "a4", "p3", "p4", "q"].iter() {
+        let attribute = format!("id=\"{}\"", id);
+        assert!(!written.contains(&attribute), "{} was not removed", id);
+    }
+    assert!(written.contains("id=\"t2\""));
+    assert_eq!(doc.find_by_id("q"), None);
+    assert!(matches!(doc.find_by_id("t2"), Some(Handle::Node(_))));
+    let read = PNMLDocument::from_xml(&written).unwrap();
+    assert_eq!(read.to_xml().unwrap(), written);
+}
+
+#[test]
+fn stale_handles() {
+    use crate::*;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let other_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let place = net.add_place(&page).unwrap();
+    // handles are plain values
+    let copy = place;
+    assert_eq!(copy.id(net).unwrap(), place.id(net).unwrap());
+
+    // the slot of a removed object is reused without reviving the old handle
+    net.remove_node(&place).unwrap();
+    let new_place = net.add_place(&page).unwrap();
+    assert!(place != new_place);
+    assert_eq!(
+        place.get_name(net).unwrap_err().kind(),
+        ErrorKind::PlaceNotFound
+    );
+    assert!(new_place.get_name(net).is_ok());
+    // a handle of another kind or of another net is not found either
+    let transition = net.add_transition(&page).unwrap();
+    let wrong_kind = match (transition, new_place) {
+        (NodeRef::TransitionRef(_), NodeRef::PlaceRef(key)) => NodeRef::TransitionRef(key),
+        _ => unreachable!(),
+    };
+    assert!(wrong_kind.get_name(net).is_err());
+    let other = doc.petri_net_data(other_ref).unwrap();
+    assert!(new_place.get_name(other).is_err());
+    assert!(other.add_place(&page).is_err());
+}
+
+#[test]
+fn validate() {
+    use crate::pnml::{Label, LabelBase, Node, Object, PNMLID};
+    use crate::validation::Problem;
+    use crate::*;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="pg">
+      <place id="p"/>
+      <place id="q"/>
+      <transition id="t"/>
+      <referencePlace id="r1" ref="r2"/>
+      <referencePlace id="r2" ref="r1"/>
+      <referenceTransition id="rt" ref="t"/>
+      <arc id="a1" source="p" target="t"/>
+      <arc id="a2" source="p" target="missing"/>
+      <arc id="a3" source="p" target="q"/>
+      <arc id="a4" source="r1" target="t"/>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let problems = |doc: &PNMLDocument| -> Vec<Problem> {
+        doc.validate().into_iter().map(|d| d.problem).collect()
+    };
+    assert_eq!(
+        problems(&doc),
+        vec![
+            Problem::DanglingArcEndpoint {
+                arc: "a2".into(),
+                node: "missing".into()
+            },
+            Problem::SameKindArc {
+                arc: "a3".into(),
+                source: "p".into(),
+                target: "q".into()
+            },
+            Problem::ReferenceCycle {
+                nodes: vec!["r1".into(), "r2".into()]
+            },
+        ]
+    );
+    assert_eq!(
+        doc.validate()[0].to_string(),
+        "net n: arc a2 connects missing which does not exist"
+    );
+
+    let node = |doc: &PNMLDocument, id| match doc.find_by_id(id) {
+        Some(Handle::Node(node)) => node,
+        _ => panic!("no node {}", id),
+    };
+    let (p, t, rt, r1) = (
+        node(&doc, "p"),
+        node(&doc, "t"),
+        node(&doc, "rt"),
+        node(&doc, "r1"),
+    );
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    net.remove_node(&r1).unwrap();
+    // break the net through the internals, like a faulty importer could
+    net.get_node_obj_mut(&rt).unwrap().object = Object::Node(Node::TransitionRef(PNMLID::new("q")));
+    net.get_node_obj_mut(&t).unwrap().labels = Some(vec![LabelBase::new(Label::PTMarking(1))]);
+    net.get_node_obj_mut(&p).unwrap().id = PNMLID::new("q");
+    assert_eq!(
+        problems(&doc),
+        vec![
+            Problem::MarkingOnTransition {
+                transition: "t".into()
+            },
+            Problem::ReferenceKindMismatch {
+                reference: "rt".into(),
+                target: "q".into()
+            },
+            Problem::DanglingArcEndpoint {
+                arc: "a1".into(),
+                node: "p".into()
+            },
+            Problem::DanglingArcEndpoint {
+                arc: "a2".into(),
+                node: "p".into()
+            },
+            Problem::DanglingArcEndpoint {
+                arc: "a2".into(),
+                node: "missing".into()
+            },
+            Problem::DanglingArcEndpoint {
+                arc: "a3".into(),
+                node: "p".into()
+            },
+            Problem::DuplicateId { id: "q".into() },
+        ]
+    );
+
+    // a consistent document has no problems
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let place = net.add_place(&page).unwrap();
+    let transition = net.add_transition(&page).unwrap();
+    net.add_reference_node(&place, &page).unwrap();
+    net.add_arc(&page, &place, &transition).unwrap();
+    assert!(doc.validate().is_empty());
+
+    // a broken file is only read leniently, then every problem is reported
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="pg">
+      <place id="p"/>
+      <transition id="t"><initialMarking><text>1</text></initialMarking></transition>
+      <referencePlace id="r1" ref="r2"/>
+      <referencePlace id="r2" ref="r1"/>
+      <referencePlace id="gone" ref="missing"/>
+      <referencePlace id="kind" ref="t"/>
+      <referencePlace id="rp" ref="p"><initialMarking><text>2</text></initialMarking></referencePlace>
+      <arc id="a1" source="p" target="nowhere"/>
+      <arc id="a2" source="rp" target="p"/>
+      <place id="twice"/>
+      <place id="twice"/>
+    </page>
+  </net>
+</pnml>"#;
+    assert!(PNMLDocument::from_xml(xml).is_err());
+    let doc = PNMLDocument::from_xml_lenient(xml).unwrap();
+    assert_eq!(
+        problems(&doc),
+        vec![
+            Problem::MarkingOnTransition {
+                transition: "t".into()
+            },
+            Problem::DanglingReference {
+                reference: "gone".into(),
+                target: "missing".into()
+            },
+            Problem::ReferenceKindMismatch {
+                reference: "kind".into(),
+                target: "t".into()
+            },
+            Problem::MarkingOnReference {
+                reference: "rp".into()
+            },
+            Problem::DanglingArcEndpoint {
+                arc: "a1".into(),
+                node: "nowhere".into()
+            },
+            Problem::SameKindArc {
+                arc: "a2".into(),
+                source: "rp".into(),
+                target: "p".into()
+            },
+            Problem::ReferenceCycle {
+                nodes: vec!["r1".into(), "r2".into()]
+            },
+            Problem::DuplicateId { id: "twice".into() },
+        ]
+    );
+}
+
+#[test]
+fn grammar() {
+    use crate::grammar_check::*;
+    use crate::*;
+    let pt_net = Grammar::pt_net();
+    let example = std::fs::File::open("pnml/Example_philosophers.xml").unwrap();
+    assert_eq!(pt_net.check_reader(example).unwrap(), vec![]);
+    let doc =
+        PNMLDocument::from_reader(std::fs::File::open("pnml/Example_philosophers.xml").unwrap())
+            .unwrap();
+    assert_eq!(doc.check_structure().unwrap(), vec![]);
+
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p">
+      <place id="a">
+        <initialMarking><text>-1</text></initialMarking>
+      </place>
+      <transition id="t">
+        <initialMarking><text>1</text></initialMarking>
+      </transition>
+      <arc id="e" source="a">
+        <inscription><text>1</text></inscription>
+        <graphics><position x="1" y="x"/></graphics>
+      </arc>
+    </page>
+    <comment>not a pt net label</comment>
+  </net>
+</pnml>"#;
+    let violations: Vec<String> = pt_net
+        .check_xml(xml)
+        .unwrap()
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    assert_eq!(
+        violations,
+        vec![
+            "/pnml/net[1]/page[1]/place[1]/initialMarking[1]/text[1]: invalid text '-1'",
+            "/pnml/net[1]/page[1]/transition[1]/initialMarking[1]: element initialMarking is not allowed here",
+            "/pnml/net[1]/page[1]/arc[1]: missing attribute target",
+            "/pnml/net[1]/page[1]/arc[1]/graphics[1]/position[1]/@y: invalid value 'x' of attribute y",
+            "/pnml/net[1]/comment[1]: element comment is not allowed here",
+        ]
+    );
+    // the core model knows no markings, but any net type
+    let core = Grammar::core_model();
+    let other_type = xml.replace("grammar/ptnet", "grammar/symmetricnet");
+    assert!(pt_net
+        .check_xml(&other_type)
+        .unwrap()
+        .iter()
+        .any(|v| v.path == "/pnml/net[1]/@type"));
+    assert!(!core
+        .check_xml(&other_type)
+        .unwrap()
+        .iter()
+        .any(|v| v.path == "/pnml/net[1]/@type"));
+    assert!(core.check_xml("<pnml").is_err());
+    // the compiled grammars are shared
+    assert_eq!(
+        Grammar::pt_net().check_xml(xml).unwrap().len(),
+        violations.len()
+    );
+}
+
+#[test]
+fn flatten() {
+    use crate::*;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p1">
+      <place id="p"/>
+      <transition id="t"/>
+      <arc id="a1" source="p" target="t"/>
+      <page id="p2">
+        <referencePlace id="rp" ref="p"/>
+        <referencePlace id="rr" ref="rp"/>
+        <transition id="u"/>
+        <arc id="a2" source="rr" target="u"/>
+      </page>
+    </page>
+    <page id="p3">
+      <referenceTransition id="rt" ref="t"/>
+      <place id="q"/>
+      <arc id="a3" source="rt" target="q"/>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let handle = |id| doc.find_by_id(id).unwrap();
+    let (p, t, rr, rt, p2, a2) = (
+        handle("p"),
+        handle("t"),
+        handle("rr"),
+        handle("rt"),
+        handle("p2"),
+        handle("a2"),
+    );
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let page = net.pages()[0];
+    let mapping = net.flatten().unwrap();
+
+    assert_eq!(mapping[&rr], p);
+    assert_eq!(mapping[&rt], t);
+    assert_eq!(mapping[&p], p);
+    assert_eq!(mapping[&a2], a2);
+    assert_eq!(mapping[&p2], Handle::Page(page));
+    assert_eq!(net.pages(), vec![page]);
+    assert_eq!(page.sub_pages(net).unwrap().count(), 0);
+    assert_eq!(net.places().count(), 2);
+    assert_eq!(net.transitions().count(), 2);
+    assert_eq!(net.arcs().count(), 3);
+    // the arcs of the reference nodes now start at the nodes they linked to
+    let p = match p {
+        Handle::Node(node) => node,
+        _ => panic!("p is no node"),
+    };
+    let postset: Vec<&str> = p
+        .postset(net)
+        .unwrap()
+        .map(|node| node.id(net).unwrap())
+        .collect();
+    assert_eq!(postset, vec!["t", "u"]);
+    assert_eq!(doc.validate(), vec![]);
+    let written = doc.to_xml().unwrap();
+    for id in ["rp", "rr", "rt", "p2", "p3"].iter() {
+        assert!(!written.contains(&format!("id=\"{}\"", id)));
+    }
+    assert_eq!(doc.check_structure().unwrap(), vec![]);
+    assert_eq!(doc.find_by_id("rp"), None);
+}
+
+#[test]
+fn reference_nodes() {
+    use crate::validation::Problem;
+    use crate::*;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let other_page = net.add_page(None);
+    let place = net.add_place(&page).unwrap();
+    let transition = net.add_transition(&page).unwrap();
+    let reference = net.add_reference_node(&place, &other_page).unwrap();
+    let chained = net.add_reference_node(&reference, &other_page).unwrap();
+    assert!(matches!(chained, NodeRef::PlaceRef(_)));
+    assert_ne!(chained, place);
+    assert_eq!(net.resolve_reference(&chained).unwrap(), place);
+    assert_eq!(net.resolve_reference(&transition).unwrap(), transition);
+
+    // arcs can start and end at reference nodes
+    let reference_transition = net.add_reference_node(&transition, &other_page).unwrap();
+    net.add_arc(&other_page, &chained, &reference_transition)
+        .unwrap();
+    assert!(net.add_arc(&other_page, &chained, &place).is_err());
+    assert_eq!(
+        place.postset(net).unwrap().collect::<Vec<_>>(),
+        vec![transition]
+    );
+    // the marking of a reference place is the marking of its place
+    let mut chained = chained;
+    chained.initial_marking(net, 2).unwrap();
+    assert_eq!(place.get_initial_marking(net).unwrap(), 2);
+    assert_eq!(reference.get_initial_marking(net).unwrap(), 2);
+    assert_eq!(net.initial_marking().unwrap().tokens(), &[2]);
+    assert!(doc.validate().is_empty());
+    assert_eq!(doc.check_structure().unwrap(), vec![]);
+
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p1">
+      <referencePlace id="r1" ref="r2"/>
+      <referencePlace id="r2" ref="r1"/>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let r1 = match doc.find_by_id("r1") {
+        Some(Handle::Node(node)) => node,
+        _ => panic!("no node r1"),
+    };
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    assert!(net.resolve_reference(&r1).is_err());
+
+    // the api never stores a marking on a reference place, the reader skips it as well
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let place = net.add_place(&page).unwrap();
+    let reference = net.add_reference_node(&place, &page).unwrap();
+    net.get_node_obj_mut(&reference)
+        .unwrap()
+        .labels
+        .get_or_insert_with(Vec::new)
+        .push(pnml::LabelBase::new(pnml::Label::PTMarking(1)));
+    let reference_id = reference.id(net).unwrap().to_string();
+    let problems: Vec<Problem> = doc.validate().into_iter().map(|d| d.problem).collect();
+    assert_eq!(
+        problems,
+        vec![Problem::MarkingOnReference {
+            reference: reference_id
+        }]
+    );
+}
+
+#[test]
+fn firing() {
+    use crate::semantics::Marking;
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let sub_page = net.add_page(None);
+    let mut input = net.add_place(&page).unwrap();
+    let output = net.add_place(&page).unwrap();
+    let transition = net.add_transition(&page).unwrap();
+    input.initial_marking(net, 3).unwrap();
+    net.add_arc(&page, &input, &transition)
+        .unwrap()
+        .inscription(net, NonZeroUsize::new(2).unwrap())
+        .unwrap();
+    // the arc of the reference place counts like an arc of the place
+    let reference = net.add_reference_node(&output, &sub_page).unwrap();
+    net.add_arc(&sub_page, &transition, &reference).unwrap();
+    net.add_arc(&page, &transition, &output).unwrap();
+
+    let initial = net.initial_marking().unwrap();
+    assert_eq!(initial, Marking::new(vec![3, 0]));
+    assert!(net.is_enabled(&transition, &initial).unwrap());
+    let next = net.fire(&transition, &initial).unwrap();
+    assert_eq!(next.tokens(), &[1, 2]);
+    assert_eq!(next[1], 2);
+    assert!(!net.is_enabled(&transition, &next).unwrap());
+    assert!(net.fire(&transition, &next).is_err());
+    assert!(net.is_enabled(&input, &initial).is_err());
+    assert!(net.is_enabled(&transition, &Marking::new(vec![3])).is_err());
+
+    // the compiled net fires by handle without compiling again
+    let compiled = net.compile().unwrap();
+    assert!(compiled.is_node_enabled(&transition, &initial).unwrap());
+    assert_eq!(compiled.fire_node(&transition, &initial).unwrap(), next);
+    assert!(compiled.fire_node(&transition, &next).is_err());
+    assert!(compiled.is_node_enabled(&reference, &initial).is_err());
+}
+
+#[test]
+fn compiled_net() {
+    use crate::semantics::Marking;
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p1">
+      <place id="a"><initialMarking><text>2</text></initialMarking></place>
+      <place id="b"/>
+      <transition id="t"/>
+      <transition id="u"/>
+      <arc id="a1" source="a" target="t"/>
+      <arc id="a2" source="t" target="b"><inscription><text>3</text></inscription></arc>
+      <arc id="a3" source="b" target="u"/>
+      <page id="p2">
+        <referencePlace id="ra" ref="a"/>
+        <arc id="a4" source="ra" target="t"/>
+        <arc id="a5" source="u" target="ra"/>
+      </page>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let ra = match doc.find_by_id("ra") {
+        Some(Handle::Node(node)) => node,
+        _ => panic!("no node ra"),
+    };
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let compiled = net.compile().unwrap();
+    assert_eq!(compiled.place_count(), 2);
+    assert_eq!(compiled.transition_count(), 2);
+    let (a, b) = (
+        compiled.place_index_of_id("a").unwrap(),
+        compiled.place_index_of_id("b").unwrap(),
+    );
+    let t = compiled.transition_index_of_id("t").unwrap();
+    assert_eq!(compiled.place_id(a), "a");
+    assert_eq!(compiled.transition_id(t), "t");
+    assert_eq!(compiled.place_index(&ra), Some(a));
+    assert_eq!(compiled.place_index_of_id("ra"), Some(a));
+    assert_eq!(compiled.transition_index_of_id("a"), None);
+    assert_eq!(compiled.place_index(&compiled.place(b)), Some(b));
+    // the arc of the reference place is added to the arc of the place
+    assert_eq!(compiled.pre(t).collect::<Vec<_>>(), vec![(a, 2)]);
+    assert_eq!(compiled.post(t).collect::<Vec<_>>(), vec![(b, 3)]);
+    assert_eq!(compiled.place_post(b).count(), 1);
+    assert_eq!(compiled.place_pre(a).count(), 1);
+
+    let initial = compiled.initial_marking();
+    assert_eq!(initial.tokens(), &[2, 0]);
+    assert_eq!(compiled.enabled(initial).collect::<Vec<_>>(), vec![t]);
+    let next = compiled.fire(t, initial).unwrap();
+    assert_eq!(next, Marking::new(vec![0, 3]));
+    assert_eq!(compiled.fire(t, &next), None);
+
+    // the compiled net is a snapshot
+    let mut arc = net.arcs().next().unwrap();
+    arc.inscription(net, NonZeroUsize::new(5).unwrap()).unwrap();
+    assert_eq!(compiled.pre(t).collect::<Vec<_>>(), vec![(a, 2)]);
+    assert_eq!(
+        net.compile().unwrap().pre(t).collect::<Vec<_>>(),
+        vec![(a, 6)]
+    );
+
+    // files may contain arcs between two places or two transitions
+    let same_kind = |arc: &str| {
+        format!(
+            r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p1">
+      <place id="a"/>
+      <referencePlace id="ra" ref="a"/>
+      <transition id="t"/>
+      <transition id="u"/>
+      {}
+    </page>
+  </net>
+</pnml>"#,
+            arc
+        )
+    };
+    for arc in [
+        r#"<arc id="x" source="a" target="ra"/>"#,
+        r#"<arc id="x" source="t" target="u"/>"#,
+    ]
+    .iter()
+    {
+        let mut doc = PNMLDocument::from_xml(&same_kind(arc)).unwrap();
+        let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+        let error = net.compile().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BipartitionViolation);
+        assert!(error.to_string().starts_with("compiling arc x"));
+    }
+    // parallel arcs whose weights cannot be added up
+    let mut doc = PNMLDocument::from_xml(&same_kind(
+        r#"<arc id="x" source="a" target="t"><inscription><text>18446744073709551615</text></inscription></arc>
+      <arc id="y" source="ra" target="t"><inscription><text>18446744073709551615</text></inscription></arc>"#,
+    ))
+    .unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    assert_eq!(net.compile().unwrap_err().kind(), ErrorKind::InvalidData);
+
+    // an arc to a node that does not exist is not left out
+    for (arc, kind) in [
+        (
+            r#"<arc id="x" source="missing" target="t"/>"#,
+            ErrorKind::PlaceNotFound,
+        ),
+        (
+            r#"<arc id="x" source="a" target="missing"/>"#,
+            ErrorKind::TransitionNotFound,
+        ),
+    ]
+    .iter()
+    {
+        let mut doc = PNMLDocument::from_xml(&same_kind(arc)).unwrap();
+        let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+        let error = net.compile().unwrap_err();
+        assert_eq!(error.kind(), *kind);
+        assert!(error.to_string().starts_with("compiling arc x"));
+        assert!(error.to_string().contains("missing"));
+    }
+}
+
+#[test]
+fn reachability() {
+    use crate::reachability::*;
+    use crate::semantics::Marking;
+    use crate::*;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    // two tokens cycling between two places
+    let mut left = net.add_place(&page).unwrap();
+    let right = net.add_place(&page).unwrap();
+    let go = net.add_transition(&page).unwrap();
+    let back = net.add_transition(&page).unwrap();
+    left.initial_marking(net, 2).unwrap();
+    net.add_arc(&page, &left, &go).unwrap();
+    net.add_arc(&page, &go, &right).unwrap();
+    net.add_arc(&page, &right, &back).unwrap();
+    net.add_arc(&page, &back, &left).unwrap();
+    let compiled = net.compile().unwrap();
+    let graph = compiled.reachability_graph(Limits::default());
+    assert!(graph.is_complete());
+    assert_eq!(graph.state_count(), 3);
+    assert_eq!(graph.edge_count(), 4);
+    assert_eq!(graph.marking(0), &Marking::new(vec![2, 0]));
+    let middle = graph.state(&Marking::new(vec![1, 1])).unwrap();
+    assert_eq!(
+        graph
+            .successors(middle)
+            .iter()
+            .map(|e| e.transition)
+            .collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert_eq!(graph.state(&Marking::new(vec![3, 0])), None);
+
+    // a transition without preset can fire forever
+    net.add_arc(&page, &go, &left).unwrap();
+    let compiled = net.compile().unwrap();
+    let limits = Limits {
+        max_states: Some(10),
+        ..Limits::default()
+    };
+    let graph = compiled.reachability_graph(limits);
+    assert_eq!(graph.exploration(), Exploration::Truncated(Limit::States));
+    assert_eq!(graph.state_count(), 10);
+    assert!(graph.is_explored(0));
+    assert!(!graph.is_explored(9));
+    assert!(graph.successors(9).is_empty());
+    let limits = Limits {
+        max_memory: Some(1000),
+        ..Limits::default()
+    };
+    let graph = compiled.reachability_graph(limits);
+    assert_eq!(graph.exploration(), Exploration::Truncated(Limit::Memory));
+    assert!(graph.state_count() < 10);
+
+    // a single state with many edges goes over the limit as well
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let mut place = net.add_place(&page).unwrap();
+    place.initial_marking(net, 1).unwrap();
+    for _ in 0..100 {
+        let transition = net.add_transition(&page).unwrap();
+        net.add_arc(&page, &place, &transition).unwrap();
+        net.add_arc(&page, &transition, &place).unwrap();
+    }
+    let compiled = net.compile().unwrap();
+    assert_eq!(
+        compiled.reachability_graph(Limits::default()).edge_count(),
+        100
+    );
+    let graph = compiled.reachability_graph(limits);
+    assert_eq!(graph.exploration(), Exploration::Truncated(Limit::Memory));
+    assert!(!graph.is_explored(0));
+}
+
+#[test]
+fn coverability() {
+    use crate::coverability::*;
+    use crate::*;
+    let xml = r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="p1">
+      <place id="start"><initialMarking><text>1</text></initialMarking></place>
+      <place id="loop"/>
+      <place id="heap"/>
+      <transition id="enter"/>
+      <transition id="produce"/>
+      <arc id="a1" source="start" target="enter"/>
+      <arc id="a2" source="enter" target="loop"/>
+      <arc id="a3" source="loop" target="produce"/>
+      <arc id="a4" source="produce" target="loop"/>
+      <arc id="a5" source="produce" target="heap"/>
+    </page>
+  </net>
+</pnml>"#;
+    let mut doc = PNMLDocument::from_xml(xml).unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let compiled = net.compile().unwrap();
+    let graph = compiled.coverability_graph();
+    let heap = compiled.place_index_of_id("heap").unwrap();
+    let start = compiled.place_index_of_id("start").unwrap();
+    assert!(!graph.is_bounded());
+    assert_eq!(graph.bound(), None);
+    assert_eq!(graph.place_bound(heap), None);
+    assert_eq!(graph.place_bound(start), Some(1));
+    assert_eq!(graph.state_count(), 3);
+    assert_eq!(graph.marking(2)[heap], Tokens::Omega);
+    let witness = graph.unboundedness_witness().unwrap();
+    let names = |transitions: &[usize]| -> Vec<String> {
+        transitions
+            .iter()
+            .map(|t| compiled.transition_id(*t).to_string())
+            .collect()
+    };
+    assert_eq!(names(&witness.prefix), vec!["enter"]);
+    assert_eq!(names(&witness.pump), vec!["produce"]);
+    assert_eq!(witness.places, vec![heap]);
+
+    // without the arc to the heap the net is 1-bounded
+    let arc = match doc.find_by_id("a5") {
+        Some(Handle::Arc(arc)) => arc,
+        _ => panic!("no arc a5"),
+    };
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    net.remove_arc(&arc).unwrap();
+    let graph = net.compile().unwrap().coverability_graph();
+    assert!(graph.is_bounded());
+    assert_eq!(graph.bound(), Some(1));
+    assert!(graph.is_k_bounded(1));
+    assert!(!graph.is_k_bounded(0));
+    assert_eq!(graph.unboundedness_witness(), None);
+    assert_eq!(graph.state_count(), 2);
+    assert_eq!(graph.successors(0).len(), 1);
+    assert!(graph.successors(2).is_empty());
+}
+
+#[test]
+fn deadlock() {
+    use crate::deadlock::*;
+    use crate::reachability::{Limit, Limits};
+    use crate::*;
+    let mut doc =
+        PNMLDocument::from_reader(std::fs::File::open("pnml/Example_philosophers.xml").unwrap())
+            .unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let deadlock = match net
+        .find_deadlock(SearchStrategy::BreadthFirst, Limits::default())
+        .unwrap()
+    {
+        DeadlockVerdict::Deadlock(deadlock) => deadlock,
+        verdict => panic!("no deadlock found: {:?}", verdict),
+    };
+    // each of the six philosophers takes one fork
+    assert_eq!(deadlock.trace.len(), 6);
+    let mut marking = net.initial_marking().unwrap();
+    for step in &deadlock.trace {
+        assert_eq!(step.transition.id(net).unwrap(), step.id);
+        assert_eq!(step.transition.get_name(net).unwrap(), step.name.as_deref());
+        assert!(step.to_string().starts_with("TAKE_"));
+        marking = net.fire(&step.transition, &marking).unwrap();
+    }
+    assert_eq!(marking, deadlock.marking);
+    assert!(net
+        .transitions()
+        .all(|t| !net.is_enabled(&t, &marking).unwrap()));
+
+    match net
+        .find_deadlock(SearchStrategy::DepthFirst, Limits::default())
+        .unwrap()
+    {
+        DeadlockVerdict::Deadlock(deadlock) => assert!(deadlock.trace.len() >= 6),
+        verdict => panic!("no deadlock found: {:?}", verdict),
+    }
+    let limits = Limits {
+        max_states: Some(3),
+        ..Limits::default()
+    };
+    assert_eq!(
+        net.find_deadlock(SearchStrategy::BreadthFirst, limits)
+            .unwrap(),
+        DeadlockVerdict::Unknown(Limit::States)
+    );
+
+    // a single place with a transition that puts the token back never gets stuck
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let mut place = net.add_place(&page).unwrap();
+    let transition = net.add_transition(&page).unwrap();
+    place.initial_marking(net, 1).unwrap();
+    net.add_arc(&page, &place, &transition).unwrap();
+    net.add_arc(&page, &transition, &place).unwrap();
+    assert_eq!(
+        net.find_deadlock(SearchStrategy::DepthFirst, Limits::default())
+            .unwrap(),
+        DeadlockVerdict::DeadlockFree
+    );
+
+    // the dead marking reached by leave is found before the limit stops the search
+    let leave = net.add_transition_with_id(&page, "leave").unwrap();
+    let outside = net.add_place(&page).unwrap();
+    net.add_arc(&page, &place, &leave).unwrap();
+    net.add_arc(&page, &leave, &outside).unwrap();
+    let limits = Limits {
+        max_states: Some(1),
+        ..Limits::default()
+    };
+    for strategy in [SearchStrategy::BreadthFirst, SearchStrategy::DepthFirst].iter() {
+        match net.find_deadlock(*strategy, limits).unwrap() {
+            DeadlockVerdict::Deadlock(deadlock) => {
+                assert_eq!(deadlock.trace.len(), 1);
+                assert_eq!(deadlock.trace[0].id, "leave");
+                assert_eq!(deadlock.marking.tokens(), &[0, 1]);
+            }
+            verdict => panic!("no deadlock found: {:?}", verdict),
+        }
+    }
+}
+
+#[test]
+fn incidence_matrices() {
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let p0 = net.add_place_with_id(&page, "p0").unwrap();
+    let p1 = net.add_place_with_id(&page, "p1").unwrap();
+    let t0 = net.add_transition_with_id(&page, "t0").unwrap();
+    let t1 = net.add_transition_with_id(&page, "t1").unwrap();
+    net.add_arc(&page, &p0, &t0)
+        .unwrap()
+        .inscription(net, NonZeroUsize::new(2).unwrap())
+        .unwrap();
+    net.add_arc(&page, &t0, &p1).unwrap();
+    // a self loop cancels out in the incidence matrix
+    net.add_arc(&page, &p1, &t1).unwrap();
+    net.add_arc(&page, &t1, &p1).unwrap();
+    let reference = net.add_reference_node(&p0, &page).unwrap();
+    net.add_arc(&page, &t1, &reference).unwrap();
+
+    let matrices = net.incidence_matrices().unwrap();
+    assert_eq!(matrices.place_ids(), &["p0", "p1"]);
+    assert_eq!(matrices.transition_ids(), &["t0", "t1"]);
+    assert_eq!(matrices.pre().to_dense(), vec![vec![2, 0], vec![0, 1]]);
+    assert_eq!(matrices.post().to_dense(), vec![vec![0, 1], vec![1, 1]]);
+    assert_eq!(
+        matrices.incidence().to_dense(),
+        vec![vec![-2, 1], vec![1, 0]]
+    );
+    assert_eq!(
+        matrices.incidence().entries(),
+        &[(0, 0, -2), (0, 1, 1), (1, 0, 1)]
+    );
+    assert_eq!(matrices.incidence().get(1, 1), 0);
+    assert_eq!(matrices.pre().rows(), 2);
+    assert_eq!(matrices.pre().columns(), 2);
+
+    // a weight beyond i64::MAX is not cut off
+    let mut arc = net.add_arc(&page, &t0, &p1).unwrap();
+    arc.inscription(net, NonZeroUsize::new(usize::MAX - 1).unwrap())
+        .unwrap();
+    let error = net.incidence_matrices().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(net.p_semiflows().is_err());
+}
+
+#[test]
+fn semiflows() {
+    use crate::reachability::Limits;
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let p0 = net.add_place_with_id(&page, "p0").unwrap();
+    let p1 = net.add_place_with_id(&page, "p1").unwrap();
+    let p2 = net.add_place_with_id(&page, "p2").unwrap();
+    let t0 = net.add_transition_with_id(&page, "t0").unwrap();
+    let t1 = net.add_transition_with_id(&page, "t1").unwrap();
+    // t0 turns two tokens of p0 into one of p1, t1 turns them back
+    net.add_arc(&page, &p0, &t0)
+        .unwrap()
+        .inscription(net, NonZeroUsize::new(2).unwrap())
+        .unwrap();
+    net.add_arc(&page, &t0, &p1).unwrap();
+    net.add_arc(&page, &p1, &t1).unwrap();
+    net.add_arc(&page, &t1, &p0)
+        .unwrap()
+        .inscription(net, NonZeroUsize::new(2).unwrap())
+        .unwrap();
+    // p2 only grows
+    net.add_arc(&page, &t1, &p2).unwrap();
+
+    let p_semiflows = net.p_semiflows().unwrap();
+    assert_eq!(p_semiflows.len(), 1);
+    assert_eq!(p_semiflows[0].weight(&p0), 1);
+    assert_eq!(p_semiflows[0].weight_of_id("p1"), 2);
+    assert_eq!(p_semiflows[0].weight(&p2), 0);
+    assert_eq!(p_semiflows[0].entries().len(), 2);
+    assert_eq!(p_semiflows[0].entries()[1].node, p1);
+    let t_semiflows = net.t_semiflows().unwrap();
+    assert_eq!(t_semiflows.len(), 0);
+
+    // p2 is consumed again, so repeating both transitions reproduces the marking
+    net.add_arc(&page, &p2, &t0).unwrap();
+    let t_semiflows = net.t_semiflows().unwrap();
+    assert_eq!(t_semiflows.len(), 1);
+    assert_eq!(t_semiflows[0].weight(&t0), 1);
+    assert_eq!(t_semiflows[0].weight(&t1), 1);
+    assert_eq!(net.p_semiflows().unwrap().len(), 2);
+
+    // the weighted tokens of the semiflows of the philosophers never change
+    let mut doc =
+        PNMLDocument::from_reader(std::fs::File::open("pnml/Example_philosophers.xml").unwrap())
+            .unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let compiled = net.compile().unwrap();
+    let semiflows = compiled.p_semiflows().unwrap();
+    let graph = compiled.reachability_graph(Limits::default());
+    assert!(graph.is_complete());
+    for semiflow in &semiflows {
+        let value = |state| {
+            semiflow
+                .entries()
+                .iter()
+                .map(|entry| entry.weight * graph.marking(state)[entry.index])
+                .sum::<usize>()
+        };
+        assert!((0..graph.state_count()).all(|state| value(state) == value(0)));
+    }
+    // every place is covered by a semiflow, so the net is bounded
+    assert!((0..compiled.place_count()).all(|place| semiflows
+        .iter()
+        .any(|s| s.entries().iter().any(|e| e.index == place))));
+    assert!(compiled.t_semiflows().unwrap().len() >= 6);
+}
+
+#[test]
+fn siphons_and_traps() {
+    use crate::deadlock::*;
+    use crate::reachability::Limits;
+    use crate::siphons::*;
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    // a token cycling between p0 and p1
+    let mut p0 = net.add_place_with_id(&page, "p0").unwrap();
+    let p1 = net.add_place_with_id(&page, "p1").unwrap();
+    let t0 = net.add_transition_with_id(&page, "t0").unwrap();
+    let t1 = net.add_transition_with_id(&page, "t1").unwrap();
+    p0.initial_marking(net, 1).unwrap();
+    net.add_arc(&page, &p0, &t0).unwrap();
+    net.add_arc(&page, &t0, &p1).unwrap();
+    net.add_arc(&page, &p1, &t1).unwrap();
+    net.add_arc(&page, &t1, &p0).unwrap();
+    let siphons = net.minimal_siphons().unwrap();
+    assert_eq!(siphons.len(), 1);
+    assert_eq!(siphons[0].places.ids(), &["p0", "p1"]);
+    assert_eq!(siphons[0].maximal_trap, siphons[0].places);
+    assert!(siphons[0].trap_marked);
+    assert_eq!(net.commoner().unwrap(), CommonerVerdict::Live);
+
+    // t2 can take the token out of the cycle
+    let p2 = net.add_place_with_id(&page, "p2").unwrap();
+    let t2 = net.add_transition_with_id(&page, "t2").unwrap();
+    net.add_arc(&page, &p1, &t2).unwrap();
+    let mut leak = net.add_arc(&page, &t2, &p2).unwrap();
+    let siphons = net.minimal_siphons().unwrap();
+    assert_eq!(siphons.len(), 1);
+    assert!(siphons[0].places.contains(&p1));
+    assert!(siphons[0].maximal_trap.is_empty());
+    match net.commoner().unwrap() {
+        CommonerVerdict::NotLive(siphon) => assert_eq!(siphon.places.len(), 2),
+        verdict => panic!("the net is not live: {:?}", verdict),
+    }
+    leak.inscription(net, NonZeroUsize::new(2).unwrap())
+        .unwrap();
+    assert_eq!(net.commoner().unwrap(), CommonerVerdict::NotFreeChoice);
+
+    // the dead marking of the philosophers empties a siphon without marked trap
+    let mut doc =
+        PNMLDocument::from_reader(std::fs::File::open("pnml/Example_philosophers.xml").unwrap())
+            .unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let dead = match net
+        .find_deadlock(SearchStrategy::BreadthFirst, Limits::default())
+        .unwrap()
+    {
+        DeadlockVerdict::Deadlock(deadlock) => deadlock.marking,
+        verdict => panic!("no deadlock found: {:?}", verdict),
+    };
+    let siphons = net.minimal_siphons().unwrap();
+    assert!(
+        siphons
+            .iter()
+            .any(|siphon| !siphon.trap_marked
+                && siphon.places.indices().iter().all(|p| dead[*p] == 0))
+    );
+    assert_eq!(net.commoner().unwrap(), CommonerVerdict::NotFreeChoice);
+}
+
+#[test]
+fn net_classes() {
+    use crate::classes::*;
+    use crate::*;
+    use std::num::NonZeroUsize;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let p0 = net.add_place_with_id(&page, "p0").unwrap();
+    let p1 = net.add_place_with_id(&page, "p1").unwrap();
+    let t0 = net.add_transition_with_id(&page, "t0").unwrap();
+    let t1 = net.add_transition_with_id(&page, "t1").unwrap();
+    net.add_arc(&page, &p0, &t0).unwrap();
+    net.add_arc(&page, &t0, &p1).unwrap();
+    net.add_arc(&page, &p1, &t1).unwrap();
+    let mut back = net.add_arc(&page, &t1, &p0).unwrap();
+    let classification = net.classify().unwrap();
+    assert_eq!(classification.classes(), NetClass::ALL.to_vec());
+
+    // a place that only gains tokens, read by t0
+    let p2 = net.add_place_with_id(&page, "p2").unwrap();
+    net.add_arc(&page, &t1, &p2).unwrap();
+    net.add_arc(&page, &p2, &t0).unwrap();
+    net.add_arc(&page, &t0, &p2).unwrap();
+    let classification = net.classify().unwrap();
+    assert!(classification.is(NetClass::Ordinary));
+    assert!(classification.is(NetClass::FreeChoice));
+    assert_eq!(
+        classification.witness(NetClass::Pure),
+        Some(&ClassWitness::SelfLoop {
+            place: "p2".into(),
+            transition: "t0".into()
+        })
+    );
+    assert_eq!(
+        classification.witness(NetClass::StateMachine),
+        Some(&ClassWitness::TransitionArity {
+            transition: "t0".into(),
+            inputs: 2,
+            outputs: 2
+        })
+    );
+    assert_eq!(
+        classification.witness(NetClass::MarkedGraph),
+        Some(&ClassWitness::PlaceArity {
+            place: "p2".into(),
+            inputs: 2,
+            outputs: 1
+        })
+    );
+    assert_eq!(
+        classification.witness(NetClass::Conservative),
+        Some(&ClassWitness::UncoveredPlace { place: "p2".into() })
+    );
+    back.inscription(net, NonZeroUsize::new(2).unwrap())
+        .unwrap();
+    let classification = net.classify().unwrap();
+    let weight = ClassWitness::ArcWeight {
+        source: "t1".into(),
+        target: "p0".into(),
+        weight: 2,
+    };
+    assert_eq!(classification.witness(NetClass::Ordinary), Some(&weight));
+    assert_eq!(classification.witness(NetClass::FreeChoice), Some(&weight));
+    assert_eq!(weight.to_string(), "the arc from t1 to p0 has the weight 2");
+
+    // the choice classes
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let a = net.add_place_with_id(&page, "a").unwrap();
+    let b = net.add_place_with_id(&page, "b").unwrap();
+    let t = net.add_transition_with_id(&page, "t").unwrap();
+    let u = net.add_transition_with_id(&page, "u").unwrap();
+    net.add_arc(&page, &a, &t).unwrap();
+    net.add_arc(&page, &b, &t).unwrap();
+    net.add_arc(&page, &a, &u).unwrap();
+    let classification = net.classify().unwrap();
+    assert_eq!(
+        classification.witness(NetClass::FreeChoice),
+        Some(&ClassWitness::SharedPlace {
+            place: "a".into(),
+            transition: "t".into(),
+            other_place: "b".into()
+        })
+    );
+    assert_eq!(
+        classification.witness(NetClass::ExtendedFreeChoice),
+        Some(&ClassWitness::DifferentPostsets {
+            first: "a".into(),
+            second: "b".into(),
+            shared: "t".into(),
+            only_first: "u".into()
+        })
+    );
+    assert!(classification.is(NetClass::AsymmetricChoice));
+    let c = net.add_place_with_id(&page, "c").unwrap();
+    let v = net.add_transition_with_id(&page, "v").unwrap();
+    net.add_arc(&page, &c, &u).unwrap();
+    net.add_arc(&page, &c, &v).unwrap();
+    assert_eq!(
+        net.classify().unwrap().witness(NetClass::AsymmetricChoice),
+        Some(&ClassWitness::CrossingPostsets {
+            first: "a".into(),
+            second: "c".into(),
+            shared: "u".into(),
+            only_first: "t".into(),
+            only_second: "v".into()
+        })
+    );
+}
+
+#[test]
+fn reduction() {
+    use crate::reduction::*;
+    use crate::*;
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net_with_id("net", Some("cycle")).unwrap();
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    // a token cycling between p0 and p1, p2 runs parallel to p1
+    let mut p0 = net.add_place_with_id(&page, "p0").unwrap();
+    let p1 = net.add_place_with_id(&page, "p1").unwrap();
+    let p2 = net.add_place_with_id(&page, "p2").unwrap();
+    let mut t0 = net.add_transition_with_id(&page, "t0").unwrap();
+    let t1 = net.add_transition_with_id(&page, "t1").unwrap();
+    let s = net.add_transition_with_id(&page, "s").unwrap();
+    p0.initial_marking(net, 1).unwrap();
+    t0.name(net, "start").unwrap();
+    net.add_arc(&page, &p0, &t0).unwrap();
+    net.add_arc(&page, &t0, &p1).unwrap();
+    net.add_arc(&page, &p1, &t1).unwrap();
+    net.add_arc(&page, &t1, &p0).unwrap();
+    net.add_arc(&page, &t0, &p2).unwrap();
+    net.add_arc(&page, &p2, &t1).unwrap();
+    net.add_arc(&page, &p0, &s).unwrap();
+    net.add_arc(&page, &s, &p0).unwrap();
+
+    let mut reduction = net
+        .reduce(&[
+            ReductionRule::ParallelPlaces,
+            ReductionRule::SelfLoopTransitions,
+        ])
+        .unwrap();
+    let rules: Vec<ReductionRule> = reduction.steps.iter().map(|step| step.rule).collect();
+    assert_eq!(
+        rules,
+        [
+            ReductionRule::ParallelPlaces,
+            ReductionRule::SelfLoopTransitions
+        ]
+    );
+    assert_eq!(
+        reduction.fate("p2"),
+        Some(&NodeFate::Merged {
+            into: "p1".into(),
+            rule: ReductionRule::ParallelPlaces
+        })
+    );
+    assert_eq!(
+        reduction.fate("s"),
+        Some(&NodeFate::Removed(ReductionRule::SelfLoopTransitions))
+    );
+    assert_eq!(reduction.fate("t0"), Some(&NodeFate::Kept));
+    assert!(reduction.document.validate().is_empty());
+    let start = reduction.representative("t0").unwrap();
+    let p1 = reduction.representative("p2").unwrap();
+    let reduced = reduction.document.petri_net_data(reduction.net).unwrap();
+    assert_eq!(reduced.places().count(), 2);
+    assert_eq!(reduced.transitions().count(), 2);
+    assert_eq!(reduced.arcs().count(), 4);
+    assert_eq!(start.get_name(reduced).unwrap(), Some("start"));
+    assert_eq!(p1.id(reduced).unwrap(), "p1");
+    assert_eq!(reduced.initial_marking().unwrap().tokens(), &[1, 0]);
+
+    // the cycle collapses into one place with a self-loop transition, the place is kept
+    // because dropping its token would make the net 0-bounded
+    let mut reduction = net.reduce(&ReductionRule::ALL).unwrap();
+    let p1 = reduction.representative("p0").unwrap();
+    let reduced = reduction.document.petri_net_data(reduction.net).unwrap();
+    assert_eq!(p1.id(reduced).unwrap(), "p1");
+    assert_eq!(reduced.places().count(), 1);
+    assert_eq!(reduced.transitions().count(), 0);
+    assert_eq!(reduced.initial_marking().unwrap().tokens(), &[1]);
+    assert_eq!(
+        reduction.fate("p0"),
+        Some(&NodeFate::Merged {
+            into: "p1".into(),
+            rule: ReductionRule::SeriesPlaces
+        })
+    );
+    assert_eq!(
+        reduction.fate("t1"),
+        Some(&NodeFate::Removed(ReductionRule::SelfLoopTransitions))
+    );
+
+    // a self-loop on an unmarked place is dead, removing it would make the net live
+    let mut doc = PNMLDocument::new();
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let p = net.add_place_with_id(&page, "p").unwrap();
+    let t = net.add_transition_with_id(&page, "t").unwrap();
+    net.add_arc(&page, &p, &t).unwrap();
+    net.add_arc(&page, &t, &p).unwrap();
+    let mut reduction = net.reduce(&ReductionRule::ALL).unwrap();
+    assert!(reduction.steps.is_empty());
+    assert_eq!(reduction.fate("t"), Some(&NodeFate::Kept));
+    let reduced = reduction.document.petri_net_data(reduction.net).unwrap();
+    assert_eq!(reduced.transitions().count(), 1);
+}
+
+#[test]
+fn reduction_bounds() {
+    use crate::reduction::*;
+    use crate::*;
+    let net = |nodes: &str| {
+        format!(
+            r#"<pnml xmlns="http://www.pnml.org/version-2009/grammar/pnml">
+  <net id="n" type="http://www.pnml.org/version-2009/grammar/ptnet">
+    <page id="pg">{}</page>
+  </net>
+</pnml>"#,
+            nodes
+        )
+    };
+    let marked = |id: &str, tokens: usize| {
+        format!(
+            r#"<place id="{}"><initialMarking><text>{}</text></initialMarking></place>"#,
+            id, tokens
+        )
+    };
+    let arcs = |arcs: &[(&str, &str)]| {
+        arcs.iter()
+            .map(|(source, target)| {
+                format!(
+                    r#"<arc id="{}_{}" source="{}" target="{}"/>"#,
+                    source, target, source, target
+                )
+            })
+            .collect::<String>()
+    };
+    let nets = [
+        // a source t1 fills p without bound, p must not be removed (series transitions)
+        format!(
+            r#"<place id="p"/><transition id="t1"/><transition id="t2"/>{}"#,
+            arcs(&[("t1", "p"), ("p", "t2")])
+        ),
+        // a 2-bounded self-loop place (redundant places)
+        format!(
+            r#"{}<transition id="t"/>{}"#,
+            marked("p", 2),
+            arcs(&[("p", "t"), ("t", "p")])
+        ),
+        // q always holds one token more than p (parallel and implicit places)
+        format!(
+            r#"{}<place id="p"/>{}<transition id="t0"/><transition id="t1"/>{}"#,
+            marked("r", 1),
+            marked("q", 1),
+            arcs(&[
+                ("r", "t0"),
+                ("t0", "p"),
+                ("t0", "q"),
+                ("p", "t1"),
+                ("q", "t1"),
+                ("t1", "r")
+            ])
+        ),
+        // q needs fewer tokens than p but holds one more (implicit places)
+        format!(
+            r#"{}<place id="p"/>{}<transition id="t0"/><transition id="t1"/><transition id="t2"/>{}"#,
+            marked("r", 1),
+            marked("q", 1),
+            arcs(&[
+                ("r", "t0"),
+                ("t0", "p"),
+                ("t0", "q"),
+                ("p", "t1"),
+                ("q", "t1"),
+                ("t1", "r"),
+                ("p", "t2"),
+                ("t2", "p")
+            ])
+        ),
+        // rules that still apply: q is parallel to p and s is covered by r,
+        // everything collapses into one place
+        format!(
+            r#"{}<place id="p"/><place id="q"/>{}<transition id="t1"/><transition id="t2"/><transition id="t3"/>{}"#,
+            marked("r", 1),
+            marked("s", 1),
+            arcs(&[
+                ("r", "t1"),
+                ("t1", "p"),
+                ("t1", "q"),
+                ("p", "t2"),
+                ("q", "t2"),
+                ("t2", "r"),
+                ("s", "t3"),
+                ("t3", "s")
+            ])
+        ),
+    ];
+    for (i, nodes) in nets.iter().enumerate() {
+        let mut doc = PNMLDocument::from_xml(&net(nodes)).unwrap();
+        let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+        let bound = net.compile().unwrap().coverability_graph().bound();
+        let mut rule_sets: Vec<Vec<ReductionRule>> =
+            ReductionRule::ALL.iter().map(|rule| vec![*rule]).collect();
+        rule_sets.push(ReductionRule::ALL.to_vec());
+        for rules in &rule_sets {
+            let mut reduction = net.reduce(rules).unwrap();
+            let reduced = reduction.document.petri_net_data(reduction.net).unwrap();
+            let reduced_bound = reduced.compile().unwrap().coverability_graph().bound();
+            assert_eq!(reduced_bound, bound, "net {} reduced with {:?}", i, rules);
+            if i == nets.len() - 1 && rules.len() > 1 {
+                assert_eq!(reduced.places().count(), 1);
+            }
+        }
+    }
+
+    // t2 follows t1, which has an input place, and has an output place itself
+    let mut doc = PNMLDocument::from_xml(&net(&format!(
+        r#"{}<place id="p"/><transition id="t1"/><transition id="t2"/>{}"#,
+        marked("r", 1),
+        arcs(&[("r", "t1"), ("t1", "p"), ("p", "t2"), ("t2", "r")])
+    )))
+    .unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let reduction = net.reduce(&[ReductionRule::SeriesTransitions]).unwrap();
+    assert_eq!(
+        reduction.fate("p"),
+        Some(&NodeFate::Removed(ReductionRule::SeriesTransitions))
+    );
+    assert_eq!(
+        reduction.fate("t2"),
+        Some(&NodeFate::Merged {
+            into: "t1".into(),
+            rule: ReductionRule::SeriesTransitions
+        })
+    );
+}
+
+#[test]
+fn simulation() {
+    use crate::simulation::*;
+    use crate::*;
+    let mut doc =
+        PNMLDocument::from_reader(std::fs::File::open("pnml/Example_philosophers.xml").unwrap())
+            .unwrap();
+    let net = doc.petri_net_data(doc.petri_nets()[0]).unwrap();
+    let run = net.simulate(&mut Random::new(7), 1000, |_| false).unwrap();
+    assert_eq!(
+        run,
+        net.simulate(&mut Random::new(7), 1000, |_| false).unwrap()
+    );
+    let mut marking = net.initial_marking().unwrap();
+    for step in &run.trace {
+        marking = net.fire(&step.transition, &marking).unwrap();
+    }
+    assert_eq!(marking, run.marking);
+    match run.stop {
+        StopReason::Deadlock => assert!(run.trace.len() < 1000),
+        StopReason::StepBound => assert_eq!(run.trace.len(), 1000),
+        stop => panic!("unexpected stop: {:?}", stop),
+    }
+    let run = net.simulate(&mut Random::new(7), 3, |_| false).unwrap();
+    assert_eq!(run.trace.len(), 3);
+    assert_eq!(run.stop, StopReason::StepBound);
+
+    // a cycle of two transitions, t1 goes first when both are enabled
+    let net_ref = doc.add_petri_net(None);
+    let net = doc.petri_net_data(net_ref).unwrap();
+    let page = net.add_page(None);
+    let mut p0 = net.add_place_with_id(&page, "p0").unwrap();
+    let p1 = net.add_place_with_id(&page, "p1").unwrap();
+    let t0 = net.add_transition_with_id(&page, "t0").unwrap();
+    let t1 = net.add_transition_with_id(&page, "t1").unwrap();
+    p0.initial_marking(net, 1).unwrap();
+    net.add_arc(&page, &p0, &t0).unwrap();
+    net.add_arc(&page, &t0, &p1).unwrap();
+    net.add_arc(&page, &p1, &t1).unwrap();
+    net.add_arc(&page, &t1, &p0).unwrap();
+    let run = net
+        .simulate(&mut Priority::new(&["t1", "t0"]), 10, |marking| {
+            marking[1] == 1
+        })
+        .unwrap();
+    let ids: Vec<&str> = run.trace.iter().map(|step| step.id.as_str()).collect();
+    assert_eq!(ids, ["t0"]);
+    assert_eq!(run.stop, StopReason::Predicate);
+    assert_eq!(run.trace[0].to_string(), "t0");
+
+    // the token leaves the cycle through t2 and the net is dead
+    let p2 = net.add_place_with_id(&page, "p2").unwrap();
+    let t2 = net.add_transition_with_id(&page, "t2").unwrap();
+    net.add_arc(&page, &p1, &t2).unwrap();
+    net.add_arc(&page, &t2, &p2).unwrap();
+    let mut policy = Priority::new(&["t2"]);
+    let run = net.simulate(&mut policy, 10, |_| false).unwrap();
+    let ids: Vec<&str> = run.trace.iter().map(|step| step.id.as_str()).collect();
+    assert_eq!(ids, ["t0", "t2"]);
+    assert_eq!(run.stop, StopReason::Deadlock);
+    assert_eq!(run.marking.tokens(), &[0, 0, 1]);
+    let mut last = Callback::new(|_, _, enabled| *enabled.last().unwrap());
+    let run = net.simulate(&mut last, 10, |_| false).unwrap();
+    assert_eq!(run.trace.len(), 2);
+    let mut disabled = Callback::new(|_, _, _| 1);
+    assert_eq!(
+        net.simulate(&mut disabled, 10, |_| false)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidData
+    );
+
+    // the priority policy ranks the transitions of another net with as many transitions anew
+    let mut other_doc = PNMLDocument::new();
+    let other_ref = other_doc.add_petri_net(None);
+    let other = other_doc.petri_net_data(other_ref).unwrap();
+    let page = other.add_page(None);
+    let mut q = other.add_place(&page).unwrap();
+    q.initial_marking(other, 1).unwrap();
+    for id in ["t2", "x", "y"].iter() {
+        let t = other.add_transition_with_id(&page, id).unwrap();
+        other.add_arc(&page, &q, &t).unwrap();
+    }
+    let run = other.simulate(&mut policy, 10, |_| false).unwrap();
+    let ids: Vec<&str> = run.trace.iter().map(|step| step.id.as_str()).collect();
+    assert_eq!(ids, ["t2"]);
+}
@@ -0,0 +1,217 @@
+//! Tool specific information (`<toolspecific>` blocks) of the pnml core model.
+//!
+//! Tools can attach arbitrary xml to nets, pages, nodes, arcs and labels.
+//! The content is not interpreted but kept as it was read, so that it can be written
+//! back without loss.
+
+use crate::pnml::*;
+use crate::pxml::{parse_raw_content, raw_content_to_string};
+use crate::*;
+use xml::reader::XmlEvent as ReaderEvent;
+
+/// A `<toolspecific>` block with its content as opaque xml
+#[derive(PartialEq, Debug, Clone)]
+pub struct ToolInfo {
+    pub(crate) tool: String,
+    pub(crate) version: String,
+    /// the events between the start and end element of the block
+    pub(crate) content: Vec<ReaderEvent>,
+}
+
+impl ToolInfo {
+    /// `content` is the raw xml inside the `<toolspecific>` element.
+    /// It may consist of several elements and text, but has to be well formed.
+    pub fn new(tool: &str, version: &str, content: &str) -> Result<Self> {
+        Ok(ToolInfo::from_events(
+            tool,
+            version,
+            parse_raw_content(content)?,
+        ))
+    }
+
+    pub(crate) fn from_events(tool: &str, version: &str, content: Vec<ReaderEvent>) -> Self {
+        ToolInfo {
+            tool: tool.into(),
+            version: version.into(),
+            content,
+        }
+    }
+
+    pub fn tool(&self) -> &str {
+        &self.tool
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// the raw xml content of the block
+    pub fn content(&self) -> Result<String> {
+        raw_content_to_string(&self.content)
+    }
+}
+
+/// Selects a label of a node or arc
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum LabelKind {
+    Name,
+    /// the marking of a place
+    InitialMarking,
+    /// the weight of an arc
+    Inscription,
+}
+
+fn tool_infos<'a>(tool_specific: &'a [ToolInfo], tool: &str) -> Vec<&'a ToolInfo> {
+    tool_specific
+        .iter()
+        .filter(|info| info.tool == tool)
+        .collect()
+}
+
+impl ObjectBase {
+    fn label_tool_specific_mut(&mut self, label: LabelKind) -> Result<&mut Vec<ToolInfo>> {
+        let id = self.id.clone();
+        let not_set = || PetriError::invalid(Some(&id), format!("{:?} is not set", label));
+        match label {
+            LabelKind::Name if self.name.text.is_some() => Ok(&mut self.name.tool_specific),
+            LabelKind::Name => Err(not_set()),
+            _ => self
+                .labels
+                .iter_mut()
+                .flatten()
+                .find(|base| base.label.kind() == Some(label))
+                .map(|base| &mut base.tool_specific)
+                .ok_or_else(not_set),
+        }
+    }
+
+    fn label_tool_specific(&self, label: LabelKind) -> &[ToolInfo] {
+        match label {
+            LabelKind::Name => &self.name.tool_specific,
+            _ => self
+                .labels
+                .iter()
+                .flatten()
+                .find(|base| base.label.kind() == Some(label))
+                .map(|base| base.tool_specific.as_slice())
+                .unwrap_or(&[]),
+        }
+    }
+}
+
+impl Label {
+    pub(crate) fn kind(&self) -> Option<LabelKind> {
+        match self {
+            Label::PTMarking(_) => Some(LabelKind::InitialMarking),
+            Label::PTAnnotation(_) => Some(LabelKind::Inscription),
+            Label::Unknown { .. } => None,
+        }
+    }
+}
+
+impl PetriNet {
+    pub fn add_tool_specific(&mut self, info: ToolInfo) -> &mut Self {
+        self.tool_specific.push(info);
+        self
+    }
+
+    /// all blocks of the given tool attached to the net
+    pub fn get_tool_specific(&self, tool: &str) -> Vec<&ToolInfo> {
+        tool_infos(&self.tool_specific, tool)
+    }
+}
+
+impl PageRef {
+    pub fn add_tool_specific(&mut self, net: &mut PetriNet, info: ToolInfo) -> Result<&mut Self> {
+        net.get_page_mut(self)?.tool_specific.push(info);
+        Ok(self)
+    }
+
+    pub fn get_tool_specific<'a>(
+        &self,
+        net: &'a PetriNet,
+        tool: &str,
+    ) -> Result<Vec<&'a ToolInfo>> {
+        Ok(tool_infos(&net.get_page(self)?.tool_specific, tool))
+    }
+}
+
+impl NodeRef {
+    pub fn add_tool_specific(&mut self, net: &mut PetriNet, info: ToolInfo) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?.tool_specific.push(info);
+        Ok(self)
+    }
+
+    pub fn get_tool_specific<'a>(
+        &self,
+        net: &'a PetriNet,
+        tool: &str,
+    ) -> Result<Vec<&'a ToolInfo>> {
+        Ok(tool_infos(&net.get_node_obj(self)?.tool_specific, tool))
+    }
+
+    /// attaches the block to a label of the node. The label has to be set before.
+    pub fn add_label_tool_specific(
+        &mut self,
+        net: &mut PetriNet,
+        label: LabelKind,
+        info: ToolInfo,
+    ) -> Result<&mut Self> {
+        net.get_node_obj_mut(self)?
+            .label_tool_specific_mut(label)?
+            .push(info);
+        Ok(self)
+    }
+
+    pub fn get_label_tool_specific<'a>(
+        &self,
+        net: &'a PetriNet,
+        label: LabelKind,
+        tool: &str,
+    ) -> Result<Vec<&'a ToolInfo>> {
+        Ok(tool_infos(
+            net.get_node_obj(self)?.label_tool_specific(label),
+            tool,
+        ))
+    }
+}
+
+impl ArcRef {
+    pub fn add_tool_specific(&mut self, net: &mut PetriNet, info: ToolInfo) -> Result<&mut Self> {
+        net.get_arc_obj_mut(self)?.tool_specific.push(info);
+        Ok(self)
+    }
+
+    pub fn get_tool_specific<'a>(
+        &self,
+        net: &'a PetriNet,
+        tool: &str,
+    ) -> Result<Vec<&'a ToolInfo>> {
+        Ok(tool_infos(&net.get_arc_obj(self)?.tool_specific, tool))
+    }
+
+    /// attaches the block to a label of the arc. The label has to be set before.
+    pub fn add_label_tool_specific(
+        &mut self,
+        net: &mut PetriNet,
+        label: LabelKind,
+        info: ToolInfo,
+    ) -> Result<&mut Self> {
+        net.get_arc_obj_mut(self)?
+            .label_tool_specific_mut(label)?
+            .push(info);
+        Ok(self)
+    }
+
+    pub fn get_label_tool_specific<'a>(
+        &self,
+        net: &'a PetriNet,
+        label: LabelKind,
+        tool: &str,
+    ) -> Result<Vec<&'a ToolInfo>> {
+        Ok(tool_infos(
+            net.get_arc_obj(self)?.label_tool_specific(label),
+            tool,
+        ))
+    }
+}
@@ -1,5 +1,11 @@
-use crate::PetriError;
+use crate::arena::Key;
+use crate::classes::ClassWitness;
+use crate::deadlock::TraceStep;
+use crate::grammar_check::Violation;
+use crate::graphics::{GradientRotation, LineShape, LineStyle};
+use crate::validation::{Diagnostic, Problem};
 use crate::{pnml::*, PNMLName};
+use crate::{ErrorKind, NodeRef, Operation, PNMLDocument, PetriError};
 use std::error::Error;
 
 impl std::fmt::Display for PNMLVersion {
@@ -11,41 +17,447 @@ impl std::fmt::Display for PNMLVersion {
     }
 }
 
+impl std::str::FromStr for PNMLVersion {
+    type Err = PetriError;
+    fn from_str(typ: &str) -> Result<Self, Self::Err> {
+        match typ {
+            "http://www.pnml.org/version-2009/grammar/ptnet" => Ok(PNMLVersion::V2009),
+            _ => Err(PetriError::invalid(
+                None,
+                format!("unsupported net type {}", typ),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for GradientRotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GradientRotation::Vertical => write!(f, "vertical"),
+            GradientRotation::Horizontal => write!(f, "horizontal"),
+            GradientRotation::Diagonal => write!(f, "diagonal"),
+        }
+    }
+}
+
+impl std::str::FromStr for GradientRotation {
+    type Err = PetriError;
+    fn from_str(rotation: &str) -> Result<Self, Self::Err> {
+        match rotation {
+            "vertical" => Ok(GradientRotation::Vertical),
+            "horizontal" => Ok(GradientRotation::Horizontal),
+            "diagonal" => Ok(GradientRotation::Diagonal),
+            _ => Err(PetriError::invalid(
+                None,
+                format!("unknown gradient rotation {}", rotation),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for LineShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineShape::Line => write!(f, "line"),
+            LineShape::Curve => write!(f, "curve"),
+        }
+    }
+}
+
+impl std::str::FromStr for LineShape {
+    type Err = PetriError;
+    fn from_str(shape: &str) -> Result<Self, Self::Err> {
+        match shape {
+            "line" => Ok(LineShape::Line),
+            "curve" => Ok(LineShape::Curve),
+            _ => Err(PetriError::invalid(
+                None,
+                format!("unknown line shape {}", shape),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for LineStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineStyle::Solid => write!(f, "solid"),
+            LineStyle::Dash => write!(f, "dash"),
+            LineStyle::Dot => write!(f, "dot"),
+        }
+    }
+}
+
+impl std::str::FromStr for LineStyle {
+    type Err = PetriError;
+    fn from_str(style: &str) -> Result<Self, Self::Err> {
+        match style {
+            "solid" => Ok(LineStyle::Solid),
+            "dash" => Ok(LineStyle::Dash),
+            "dot" => Ok(LineStyle::Dot),
+            _ => Err(PetriError::invalid(
+                None,
+                format!("unknown line style {}", style),
+            )),
+        }
+    }
+}
+
+impl PetriError {
+    /// The kind of the error. Context wrappers are looked through.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            PetriError::BipartitionViolation { .. } => ErrorKind::BipartitionViolation,
+            PetriError::PlaceNotFound { .. } => ErrorKind::PlaceNotFound,
+            PetriError::TransitionNotFound { .. } => ErrorKind::TransitionNotFound,
+            PetriError::ArcNotFound { .. } => ErrorKind::ArcNotFound,
+            PetriError::PageNotFound { .. } => ErrorKind::PageNotFound,
+            PetriError::NetNotFound { .. } => ErrorKind::NetNotFound,
+            PetriError::DuplicateId { .. } => ErrorKind::DuplicateId,
+            PetriError::InvalidData { .. } => ErrorKind::InvalidData,
+            PetriError::CorruptedData { .. } => ErrorKind::CorruptedData,
+            PetriError::XmlWriterError(_) => ErrorKind::XmlWriterError,
+            PetriError::XmlReaderError(_) => ErrorKind::XmlReaderError,
+            PetriError::Context { source, .. } => source.kind(),
+        }
+    }
+
+    /// The innermost error without any context
+    pub fn root(&self) -> &PetriError {
+        match self {
+            PetriError::Context { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    /// wraps the error with the operation that failed on the object with the given id
+    pub(crate) fn context(self, operation: Operation, id: Option<&PNMLID>) -> Self {
+        PetriError::Context {
+            operation,
+            id: id.map(|id| id.0.clone()),
+            source: Box::new(self),
+        }
+    }
+
+    /// fills in the page of a place, transition or arc that was not found, where it is not known yet
+    pub(crate) fn on_page(self, page_id: &PNMLID) -> Self {
+        let on_page = Some(page_id.0.clone());
+        match self {
+            PetriError::PlaceNotFound {
+                handle,
+                id,
+                page: None,
+            } => PetriError::PlaceNotFound {
+                handle,
+                id,
+                page: on_page,
+            },
+            PetriError::TransitionNotFound {
+                handle,
+                id,
+                page: None,
+            } => PetriError::TransitionNotFound {
+                handle,
+                id,
+                page: on_page,
+            },
+            PetriError::ArcNotFound { handle, page: None } => PetriError::ArcNotFound {
+                handle,
+                page: on_page,
+            },
+            PetriError::Context {
+                operation,
+                id,
+                source,
+            } => PetriError::Context {
+                operation,
+                id,
+                source: Box::new(source.on_page(page_id)),
+            },
+            error => error,
+        }
+    }
+
+    pub(crate) fn invalid(id: Option<&PNMLID>, reason: impl Into<String>) -> Self {
+        PetriError::InvalidData {
+            id: id.map(|id| id.0.clone()),
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn corrupted(id: Option<&PNMLID>, reason: impl Into<String>) -> Self {
+        PetriError::CorruptedData {
+            id: id.map(|id| id.0.clone()),
+            reason: reason.into(),
+        }
+    }
+}
+
 impl Error for PetriError {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            PetriError::BipartitionViolation => "Bipartition Violation: Edges cannot lead to identical Node types. They are only allowed from places to transitions or vice versa",
-            PetriError::PlaceNotFound => "Place Not Found: There is no corresponding place in the internal representation",
-            PetriError::TransitionNotFound => "Transition Not Found: There is no corresponding transition in the internal representation",
-            PetriError::ObjectNotFound => "Object Not Found: There is no corresponding object in the internal representation",
-            PetriError::PageNotFound => "Page Not Found: Could not find (sub)page in the given path",
-            PetriError::NetNotFound => "Net Not Found: Could not find the referenced petri net in the pnml document",
-            PetriError::InvalidData(_) => "Invalid Data: Tried to use data in a place where it do not belong",
-            PetriError::CorruptedData(_) => "Corrupted Data: There where objects in arrays there they shouldn't be",
-            PetriError::XmlWriterError(error) => error.description()
+            PetriError::XmlWriterError(error) => Some(error),
+            PetriError::XmlReaderError(error) => Some(error),
+            PetriError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
         }
     }
 }
 
+/// Writes a message like "place n0_p0_o2 not found on page n0_p0".
+/// If the id is unknown the position the handle points to is written instead.
+fn write_not_found(
+    f: &mut std::fmt::Formatter<'_>,
+    typ: &str,
+    id: &Option<String>,
+    position: Option<String>,
+    page: &Option<String>,
+) -> std::fmt::Result {
+    write!(f, "{}", typ)?;
+    match (id, position) {
+        (Some(id), _) => write!(f, " {}", id)?,
+        (None, Some(position)) => write!(f, " {}", position)?,
+        (None, None) => {}
+    }
+    write!(f, " not found")?;
+    if let Some(page) = page {
+        write!(f, " on page {}", page)?;
+    }
+    Ok(())
+}
+
+fn key_position(key: Key) -> String {
+    format!("with handle #{}.{}", key.index(), key.generation())
+}
+
+fn node_position(node: &Option<NodeRef>) -> Option<String> {
+    node.as_ref().map(|node| key_position(node.key()))
+}
+
 impl std::fmt::Display for PetriError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let msg = match self {
-            PetriError::InvalidData(msg) => format!("{}: {}", self.description(), msg),
-            PetriError::CorruptedData(msg) => format!("{}: {}", self.description(), msg),
-            _ => format!("{}", self.description()),
-        };
-        write!(f, "{}", msg)
+        match self {
+            PetriError::BipartitionViolation { source, target } => write!(
+                f,
+                "arcs cannot connect two nodes of the same type ({} -> {})",
+                source, target
+            ),
+            PetriError::PlaceNotFound { handle, id, page } => {
+                write_not_found(f, "place", id, node_position(handle), page)
+            }
+            PetriError::TransitionNotFound { handle, id, page } => {
+                write_not_found(f, "transition", id, node_position(handle), page)
+            }
+            PetriError::ArcNotFound { handle, page } => {
+                let position = handle.as_ref().map(|arc| key_position(arc.0));
+                write_not_found(f, "arc", &None, position, page)
+            }
+            PetriError::PageNotFound { handle, id } => {
+                let position = handle.as_ref().map(|page| key_position(page.0));
+                write_not_found(f, "page", id, position, &None)
+            }
+            PetriError::NetNotFound { handle } => write!(f, "net {} not found", handle.0),
+            PetriError::DuplicateId { id } => write!(f, "id {} is already used", id),
+            PetriError::InvalidData {
+                id: Some(id),
+                reason,
+            } => write!(f, "invalid data for {}: {}", id, reason),
+            PetriError::InvalidData { id: None, reason } => write!(f, "invalid data: {}", reason),
+            PetriError::CorruptedData {
+                id: Some(id),
+                reason,
+            } => write!(f, "corrupted data at {}: {}", id, reason),
+            PetriError::CorruptedData { id: None, reason } => {
+                write!(f, "corrupted data: {}", reason)
+            }
+            PetriError::XmlWriterError(error) => write!(f, "could not write xml: {}", error),
+            PetriError::XmlReaderError(error) => write!(f, "could not read xml: {}", error),
+            PetriError::Context {
+                operation,
+                id: Some(id),
+                source,
+            } => write!(f, "{} {}: {}", operation, id, source),
+            PetriError::Context {
+                operation,
+                id: None,
+                source,
+            } => write!(f, "{}: {}", operation, source),
+        }
     }
 }
 
-impl std::fmt::Debug for PetriError {
+impl std::fmt::Display for Operation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let msg = match self {
-            PetriError::InvalidData(msg) => format!("{}: {}", self.description(), msg),
-            PetriError::CorruptedData(msg) => format!("{}: {}", self.description(), msg),
-            _ => format!("{}", self.description()),
+        let operation = match self {
+            Operation::AddPage => "adding page",
+            Operation::AddPlace => "adding place",
+            Operation::AddTransition => "adding transition",
+            Operation::AddArc => "adding arc",
+            Operation::AddReferenceNode => "adding reference node",
+            Operation::RemovePage => "removing page",
+            Operation::RemoveNode => "removing node",
+            Operation::RemoveArc => "removing arc",
+            Operation::MoveNode => "moving node",
+            Operation::Flatten => "flattening net",
+            Operation::Fire => "firing transition",
+            Operation::Compile => "compiling arc",
+            Operation::ResolveSource => "resolving source",
+            Operation::ResolveTarget => "resolving target",
+            Operation::ResolveReference => "resolving reference node",
+            Operation::SetName => "naming",
+            Operation::SetInitialMarking => "setting initial marking of",
+            Operation::SetInscription => "setting inscription of",
+            Operation::ReadNet => "reading net",
+            Operation::ReadPage => "reading page",
+            Operation::ReadNode => "reading node",
+            Operation::ReadArc => "reading arc",
         };
-        write!(f, "{}", msg)
+        write!(f, "{}", operation)
+    }
+}
+
+/// the name of the transition, its id if it has none
+impl std::fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(net) = &self.net {
+            write!(f, "net {}: ", net)?;
+        }
+        write!(f, "{}", self.problem)
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::DanglingArcEndpoint { arc, node } => {
+                write!(f, "arc {} connects {} which does not exist", arc, node)
+            }
+            Problem::SameKindArc {
+                arc,
+                source,
+                target,
+            } => write!(
+                f,
+                "arc {} connects two nodes of the same type ({} -> {})",
+                arc, source, target
+            ),
+            Problem::DanglingReference { reference, target } => write!(
+                f,
+                "reference node {} links to {} which does not exist",
+                reference, target
+            ),
+            Problem::ReferenceKindMismatch { reference, target } => write!(
+                f,
+                "reference node {} links to {} which is of a different node type",
+                reference, target
+            ),
+            Problem::ReferenceCycle { nodes } => {
+                write!(f, "reference nodes form a cycle ({})", nodes.join(" -> "))
+            }
+            Problem::MarkingOnTransition { transition } => {
+                write!(f, "transition {} has an initial marking", transition)
+            }
+            Problem::MarkingOnReference { reference } => {
+                write!(f, "reference node {} has an initial marking", reference)
+            }
+            Problem::DuplicateId { id } => write!(f, "id {} is used more than once", id),
+            Problem::UnresolvedPage { parent: Some(page) } => {
+                write!(f, "page {} lists a sub page that does not exist", page)
+            }
+            Problem::UnresolvedPage { parent: None } => {
+                write!(f, "the net lists a page that does not exist")
+            }
+            Problem::UnresolvedObject { page } => {
+                write!(f, "page {} lists an object that does not exist", page)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ClassWitness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClassWitness::ArcWeight {
+                source,
+                target,
+                weight,
+            } => write!(
+                f,
+                "the arc from {} to {} has the weight {}",
+                source, target, weight
+            ),
+            ClassWitness::TransitionArity {
+                transition,
+                inputs,
+                outputs,
+            } => write!(
+                f,
+                "transition {} has {} input and {} output places",
+                transition, inputs, outputs
+            ),
+            ClassWitness::PlaceArity {
+                place,
+                inputs,
+                outputs,
+            } => write!(
+                f,
+                "place {} has {} input and {} output transitions",
+                place, inputs, outputs
+            ),
+            ClassWitness::SharedPlace {
+                place,
+                transition,
+                other_place,
+            } => write!(
+                f,
+                "place {} has several output transitions and {} has the other input place {}",
+                place, transition, other_place
+            ),
+            ClassWitness::DifferentPostsets {
+                first,
+                second,
+                shared,
+                only_first,
+            } => write!(
+                f,
+                "places {} and {} share {}, but only {} is an output of {}",
+                first, second, shared, only_first, first
+            ),
+            ClassWitness::CrossingPostsets {
+                first,
+                second,
+                shared,
+                only_first,
+                only_second,
+            } => write!(
+                f,
+                "places {} and {} share {}, but only {} is an output of {} and only {} of {}",
+                first, second, shared, only_first, first, only_second, second
+            ),
+            ClassWitness::SelfLoop { place, transition } => write!(
+                f,
+                "transition {} consumes from and produces on place {}",
+                transition, place
+            ),
+            ClassWitness::UncoveredPlace { place } => {
+                write!(f, "place {} is not covered by a P-semiflow", place)
+            }
+        }
     }
 }
 
@@ -55,11 +467,24 @@ impl From<xml::writer::Error> for PetriError {
     }
 }
 
+impl From<xml::reader::Error> for PetriError {
+    fn from(error: xml::reader::Error) -> Self {
+        PetriError::XmlReaderError(error)
+    }
+}
+
+impl Default for PNMLDocument {
+    fn default() -> Self {
+        PNMLDocument::new()
+    }
+}
+
 impl From<Option<&str>> for PNMLName {
     fn from(name: Option<&str>) -> Self {
-        match name {
-            Some(n) => PNMLName(Some(String::from(n))),
-            None => PNMLName(None),
+        PNMLName {
+            text: name.map(String::from),
+            graphics: None,
+            tool_specific: Vec::new(),
         }
     }
 }
@@ -0,0 +1,155 @@
+//! Read-only traversal of a net.
+//!
+//! The iterators return the same handles as the functions that create the objects,
+//! so they can be used to query or change the objects afterwards.
+//! Reference nodes (RefPlace/RefTrans) are no places or transitions on their own,
+//! they are resolved to the node they link to wherever the structure of the net is concerned.
+
+use crate::pnml::*;
+use crate::*;
+
+impl PetriNet {
+    /// All places of the net on all (sub) pages, without reference places
+    pub fn places(&self) -> impl Iterator<Item = NodeRef> {
+        self.nodes(|node| matches!(node, Node::Place)).into_iter()
+    }
+
+    /// All transitions of the net on all (sub) pages, without reference transitions
+    pub fn transitions(&self) -> impl Iterator<Item = NodeRef> {
+        self.nodes(|node| matches!(node, Node::Transition))
+            .into_iter()
+    }
+
+    /// All arcs of the net on all (sub) pages.
+    /// The source and sink of an arc may be reference nodes.
+    /// Arcs with a source or sink that does not exist (only possible in read documents) are skipped.
+    pub fn arcs(&self) -> impl Iterator<Item = ArcRef> {
+        self.objects()
+            .into_iter()
+            .filter_map(|(key, obj)| match &obj.object {
+                Object::Arc(source, sink)
+                    if self.node_by_id(source).is_some() && self.node_by_id(sink).is_some() =>
+                {
+                    Some(ArcRef(key))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn nodes(&self, is_kind: fn(&Node) -> bool) -> Vec<NodeRef> {
+        self.objects()
+            .into_iter()
+            .filter_map(|(key, obj)| match &obj.object {
+                Object::Node(node) if is_kind(node) => Some(NodeRef::new(key, node)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Follows a reference node, and the reference nodes it links to, to the place or
+    /// transition it represents. Places and transitions are returned as they are.
+    /// Fails if the references form a cycle or link to a node that does not exist.
+    pub fn resolve_reference(&self, node: &NodeRef) -> Result<NodeRef> {
+        self.follow_references(node)
+            .map_err(|e| e.context(Operation::ResolveReference, None))
+    }
+
+    fn follow_references(&self, node: &NodeRef) -> Result<NodeRef> {
+        let mut visited = Vec::new();
+        let mut current = *node;
+        loop {
+            let obj = self.get_node_obj(&current)?;
+            match &obj.object {
+                Object::Node(Node::PlaceRef(target))
+                | Object::Node(Node::TransitionRef(target)) => {
+                    if visited.contains(target) {
+                        return Err(PetriError::invalid(
+                            Some(&obj.id),
+                            "reference nodes form a cycle",
+                        ));
+                    }
+                    visited.push(obj.id.clone());
+                    current = self.node_by_id(target).ok_or_else(|| {
+                        PetriError::invalid(
+                            Some(&obj.id),
+                            format!("links to {} which does not exist", target.0),
+                        )
+                    })?;
+                }
+                _ => return Ok(current),
+            }
+        }
+    }
+
+    /// the nodes at the other end of the arcs that end (`incoming`) or start at the node
+    fn neighbours(&self, node: &NodeRef, incoming: bool) -> Result<Vec<NodeRef>> {
+        let node = self.resolve_reference(node)?;
+        let mut neighbours = Vec::new();
+        for arc in self.arcs() {
+            let (this, other) = if incoming {
+                (arc.sink(self)?, arc.source(self)?)
+            } else {
+                (arc.source(self)?, arc.sink(self)?)
+            };
+            if self.resolve_reference(&this)? == node {
+                let other = self.resolve_reference(&other)?;
+                if !neighbours.contains(&other) {
+                    neighbours.push(other);
+                }
+            }
+        }
+        Ok(neighbours)
+    }
+}
+
+impl PageRef {
+    /// the direct sub pages of the page
+    pub fn sub_pages(&self, net: &PetriNet) -> Result<impl Iterator<Item = PageRef>> {
+        let page = PetriNet::obj_to_page(net.get_page(self)?)?;
+        let sub_pages: Vec<PageRef> = page.sub_pages.iter().map(|key| PageRef(*key)).collect();
+        Ok(sub_pages.into_iter())
+    }
+}
+
+impl NodeRef {
+    /// The nodes with an arc to this node.
+    /// Reference nodes are resolved, each node is returned once.
+    pub fn preset(&self, net: &PetriNet) -> Result<impl Iterator<Item = NodeRef>> {
+        Ok(net.neighbours(self, true)?.into_iter())
+    }
+
+    /// The nodes with an arc from this node.
+    /// Reference nodes are resolved, each node is returned once.
+    pub fn postset(&self, net: &PetriNet) -> Result<impl Iterator<Item = NodeRef>> {
+        Ok(net.neighbours(self, false)?.into_iter())
+    }
+}
+
+impl ArcRef {
+    /// the node the arc starts at, this may be a reference node
+    pub fn source(&self, net: &PetriNet) -> Result<NodeRef> {
+        self.endpoint(net, true)
+    }
+
+    /// the node the arc ends at, this may be a reference node
+    pub fn sink(&self, net: &PetriNet) -> Result<NodeRef> {
+        self.endpoint(net, false)
+    }
+
+    fn endpoint(&self, net: &PetriNet, source: bool) -> Result<NodeRef> {
+        let obj = net.get_arc_obj(self)?;
+        let id = match &obj.object {
+            Object::Arc(id, _) if source => id,
+            Object::Arc(_, id) => id,
+            _ => return Err(self.not_found()),
+        };
+        net.node_by_id(id).ok_or_else(|| {
+            PetriError::invalid(
+                Some(&obj.id),
+                format!("connects {} which does not exist", id.0),
+            )
+        })
+    }
+}
@@ -0,0 +1,242 @@
+//! Consistency check of a whole document.
+//!
+//! The functions that build a net reject most inconsistencies right away, but a document
+//! read from a file may contain arcs to nodes that do not exist, and reference nodes can
+//! hide arcs between two places or two transitions. [`PNMLDocument::validate`] finds
+//! these problems without stopping at the first one. [`PNMLDocument::from_reader`] fails
+//! on duplicate ids and broken reference nodes, read a file with
+//! [`PNMLDocument::from_reader_lenient`] to get all of its problems reported.
+
+use crate::arena::Key;
+use crate::pnml::*;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+/// A problem found by [`PNMLDocument::validate`]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Diagnostic {
+    /// the id of the net the problem is in, None for problems of the whole document
+    pub net: Option<String>,
+    pub problem: Problem,
+}
+
+/// The kinds of problems reported in a [`Diagnostic`], with the ids of the objects involved
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Problem {
+    /// The source or target of the arc does not exist
+    DanglingArcEndpoint { arc: String, node: String },
+    /// The arc connects two places or two transitions once reference nodes are resolved
+    SameKindArc {
+        arc: String,
+        source: String,
+        target: String,
+    },
+    /// The node linked by the reference node does not exist
+    DanglingReference { reference: String, target: String },
+    /// A reference place links to a transition or a reference transition to a place
+    ReferenceKindMismatch { reference: String, target: String },
+    /// The reference nodes link to each other in a circle and never reach a node
+    ReferenceCycle { nodes: Vec<String> },
+    /// Only places can carry an initial marking
+    MarkingOnTransition { transition: String },
+    /// The marking of a reference place belongs to the place it links to
+    MarkingOnReference { reference: String },
+    /// More than one object of the document uses the id
+    DuplicateId { id: String },
+    /// The page (None for the net itself) lists a sub page that is missing or no page
+    UnresolvedPage { parent: Option<String> },
+    /// The page lists a node or arc that is missing or no node or arc
+    UnresolvedObject { page: String },
+}
+
+impl PNMLDocument {
+    /// Checks the consistency of all nets and returns every problem found.
+    /// An empty list means the document can be analysed safely.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for net in &self.petri_nets {
+            *counts.entry(&net.id.0).or_default() += 1;
+            for (_, obj) in net.reachable_objects(&mut Vec::new()) {
+                *counts.entry(&obj.id.0).or_default() += 1;
+            }
+            diagnostics.extend(net.validate());
+        }
+        let mut duplicates: Vec<&str> = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(id, _)| id)
+            .collect();
+        duplicates.sort_unstable();
+        diagnostics.extend(duplicates.into_iter().map(|id| Diagnostic {
+            net: None,
+            problem: Problem::DuplicateId { id: id.into() },
+        }));
+        diagnostics
+    }
+}
+
+impl PetriNet {
+    /// Checks the consistency of the net. Ids are only unique in the whole document,
+    /// so they are checked by [`PNMLDocument::validate`].
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut problems = Vec::new();
+        let objects = self.reachable_objects(&mut problems);
+        let nodes: HashMap<&str, &Node> = objects
+            .iter()
+            .filter_map(|(_, obj)| match &obj.object {
+                Object::Node(node) => Some((obj.id.0.as_str(), node)),
+                _ => None,
+            })
+            .collect();
+        let mut cycles = HashSet::new();
+        for (_, obj) in &objects {
+            match &obj.object {
+                Object::Node(Node::PlaceRef(target))
+                | Object::Node(Node::TransitionRef(target)) => {
+                    let is_place = matches!(obj.object, Object::Node(Node::PlaceRef(_)));
+                    match nodes.get(target.0.as_str()) {
+                        None => problems.push(Problem::DanglingReference {
+                            reference: obj.id.0.clone(),
+                            target: target.0.clone(),
+                        }),
+                        Some(node) if is_place_like(node) != is_place => {
+                            problems.push(Problem::ReferenceKindMismatch {
+                                reference: obj.id.0.clone(),
+                                target: target.0.clone(),
+                            })
+                        }
+                        Some(_) => {}
+                    }
+                    if let Err(Some(cycle)) = resolve(&nodes, &obj.id.0) {
+                        cycles.insert(cycle);
+                    }
+                    if has_marking(obj) {
+                        problems.push(Problem::MarkingOnReference {
+                            reference: obj.id.0.clone(),
+                        });
+                    }
+                }
+                Object::Node(Node::Transition) if has_marking(obj) => {
+                    problems.push(Problem::MarkingOnTransition {
+                        transition: obj.id.0.clone(),
+                    });
+                }
+                Object::Arc(source, target) => {
+                    for node in [source, target].iter() {
+                        if !nodes.contains_key(node.0.as_str()) {
+                            problems.push(Problem::DanglingArcEndpoint {
+                                arc: obj.id.0.clone(),
+                                node: node.0.clone(),
+                            });
+                        }
+                    }
+                    // dangling references and cycles are reported on their own
+                    if let (Ok(source_kind), Ok(target_kind)) =
+                        (resolve(&nodes, &source.0), resolve(&nodes, &target.0))
+                    {
+                        if source_kind == target_kind {
+                            problems.push(Problem::SameKindArc {
+                                arc: obj.id.0.clone(),
+                                source: source.0.clone(),
+                                target: target.0.clone(),
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut cycles: Vec<Vec<String>> = cycles.into_iter().collect();
+        cycles.sort();
+        problems.extend(
+            cycles
+                .into_iter()
+                .map(|nodes| Problem::ReferenceCycle { nodes }),
+        );
+        problems
+            .into_iter()
+            .map(|problem| Diagnostic {
+                net: Some(self.id.0.clone()),
+                problem,
+            })
+            .collect()
+    }
+
+    /// All pages, nodes and arcs that can be reached from the pages of the net.
+    /// Keys that do not resolve to the expected kind of object are reported in `problems`.
+    fn reachable_objects(&self, problems: &mut Vec<Problem>) -> Vec<(Key, &ObjectBase)> {
+        let mut objects = Vec::new();
+        let mut pages: Vec<(Option<&ObjectBase>, Key)> =
+            self.pages.iter().rev().map(|key| (None, *key)).collect();
+        let mut visited = HashSet::new();
+        while let Some((parent, key)) = pages.pop() {
+            // a page listed twice would be visited forever
+            if !visited.insert(key) {
+                continue;
+            }
+            let page = self.objects.get(key);
+            let content = match page.map(|page| &page.object) {
+                Some(Object::Page(content)) => content,
+                _ => {
+                    problems.push(Problem::UnresolvedPage {
+                        parent: parent.map(|parent| parent.id.0.clone()),
+                    });
+                    continue;
+                }
+            };
+            let page = match page {
+                Some(page) => page,
+                None => continue,
+            };
+            objects.push((key, page));
+            for key in &content.objects {
+                match self.objects.get(*key) {
+                    Some(obj) if !matches!(obj.object, Object::Page(_)) => {
+                        objects.push((*key, obj))
+                    }
+                    _ => problems.push(Problem::UnresolvedObject {
+                        page: page.id.0.clone(),
+                    }),
+                }
+            }
+            pages.extend(content.sub_pages.iter().rev().map(|key| (Some(page), *key)));
+        }
+        objects
+    }
+}
+
+fn is_place_like(node: &Node) -> bool {
+    matches!(node, Node::Place | Node::PlaceRef(_))
+}
+
+fn has_marking(obj: &ObjectBase) -> bool {
+    obj.labels
+        .iter()
+        .flatten()
+        .any(|label| matches!(label.label, Label::PTMarking(_)))
+}
+
+/// Follows reference nodes from the node with the id and tells if the node at the end is a place.
+/// The error contains the cycle if the references form one, rotated to start at the smallest id.
+fn resolve(
+    nodes: &HashMap<&str, &Node>,
+    id: &str,
+) -> std::result::Result<bool, Option<Vec<String>>> {
+    let mut chain: Vec<&str> = Vec::new();
+    let mut current = id;
+    loop {
+        if let Some(start) = chain.iter().position(|other| *other == current) {
+            let mut cycle: Vec<String> = chain[start..].iter().map(|id| id.to_string()).collect();
+            let smallest = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap_or(0);
+            cycle.rotate_left(smallest);
+            return Err(Some(cycle));
+        }
+        chain.push(current);
+        match nodes.get(current) {
+            Some(Node::PlaceRef(target)) | Some(Node::TransitionRef(target)) => current = &target.0,
+            Some(node) => return Ok(is_place_like(node)),
+            None => return Err(None),
+        }
+    }
+}